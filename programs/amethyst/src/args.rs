@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::state::position::Direction;
+use crate::state::{order::TriggerCondition, position::Direction, vault::OracleType};
 
 #[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct CreateConfigArgs {
@@ -16,13 +16,100 @@ pub struct CreateConfigArgs {
     pub stable_swap_fee_bps: u16,
     /// The fee applied to margin position.
     pub margin_fee_bps: u16,
+    /// The minimum collateral a position must retain, relative to its size.
+    pub maintenance_margin_bps: u16,
+    /// The fee paid to the liquidator of a position, denominated in USD.
+    pub liquidation_fee_usd: u64,
+    /// The leverage partially liquidated positions are brought back to, represented in basis points.
+    pub liquidation_target_leverage: u32,
+    /// The minimum number of seconds between two funding payments of a position made through
+    /// `pay_funding`, zero to allow them at any time.
+    pub min_funding_interval: u32,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct CreateOrderArgs {
+    /// The trigger price of the index token.
+    pub trigger_price: u64,
+    /// The condition under which the order triggers.
+    pub trigger_condition: TriggerCondition,
+    /// The size to close, denominated in USD.
+    pub size_delta: u64,
+    /// The fee paid to the keeper executing the order, in lamports.
+    pub execution_fee: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct CreateIncreaseOrderArgs {
+    /// The collateral to escrow, in native token amount.
+    pub collateral: u64,
+    /// The size to open or increase the position by, denominated in USD.
+    pub size: u64,
+    /// The direction of the position.
+    pub direction: Direction,
+    /// The limit price of the index token.
+    pub limit_price: u64,
+    /// The timestamp after which the order can no longer be executed, or zero if it never expires.
+    pub expires_at: u64,
+    /// The fee paid to the keeper executing the order, in lamports.
+    pub execution_fee: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct CreatePositionRequestArgs {
+    /// The collateral to escrow, in native token amount.
+    pub collateral: u64,
+    /// The size to open or increase the position by, denominated in USD.
+    pub size: u64,
+    /// The direction of the position.
+    pub direction: Direction,
+    /// The fee paid to the keeper executing the request, in lamports.
+    pub execution_fee: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct CreateVaultArgs {
+    /// Whether the vault represents a stable coin.
+    pub is_stable: bool,
+    /// Whether the vault has dynamic fees.
+    pub has_dynamic_fees: bool,
+    /// The maximum allowed leverage for this vault, represented in basis points.
+    pub max_leverage: u32,
+    /// The type of oracle price feed, whose account is bound to the vault.
+    pub oracle_type: OracleType,
+    /// The maximum confidence interval threshold, used when the oracle type is Pyth.
+    pub pyth_max_confidence_interval: u64,
+    /// The maximum confidence interval threshold, used when the oracle type is Switchboard V2.
+    pub switchboard_max_confidence_interval: f64,
+    /// Whether the oracle of the other type, whose account is bound to the vault, is used as a
+    /// secondary oracle.
+    pub has_secondary_oracle: bool,
+    /// The maximum deviation between the prices of both oracles, represented in basis points,
+    /// zero when it is not checked.
+    pub max_oracle_deviation_bps: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct DecreasePositionArgs {
+    /// The size to decrease the position by, denominated in USD.
+    pub size_delta: u64,
+    /// The collateral to withdraw from the position, denominated in USD.
+    pub collateral_delta: Option<u64>,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct DepositLiquidityArgs {
+    /// The amount of tokens to deposit.
+    pub amount: u64,
+    /// The minimum amount of LP tokens to receive.
+    pub min_lp_out: u64,
 }
 
 #[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct EnterPositionArgs {
-    /// The user's collateral to be used.
+    /// The user's collateral to be used, in native amount of the collateral token.
     pub collateral: u64,
-    /// The total position size.
+    /// The total position size, denominated in USD.
     pub size: u64,
     /// The direction of the position.
     pub direction: Direction,
@@ -35,3 +122,68 @@ pub struct SwapArgs {
     /// The minimum amount of asset B.
     pub min_amount_out: u64,
 }
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct SwapRouteArgs {
+    /// The amount of the input asset to be swapped.
+    pub amount_in: u64,
+    /// The minimum amount of the output asset at the end of the route.
+    pub min_amount_out: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateFundingParamsArgs {
+    /// The funding rate per hour at zero utilisation, left unchanged when not given.
+    pub base_rate: Option<u32>,
+    /// The funding rate per hour added over the full utilisation range, below the kink, left
+    /// unchanged when not given.
+    pub slope_below_kink: Option<u32>,
+    /// The utilisation at which the slope above the kink applies, represented in basis points,
+    /// left unchanged when not given.
+    pub kink_utilisation_bps: Option<u16>,
+    /// The funding rate per hour added over the full utilisation range, above the kink, left
+    /// unchanged when not given.
+    pub slope_above_kink: Option<u32>,
+    /// The maximum funding rate per hour, zero to leave the funding rate uncapped, left unchanged
+    /// when not given. It does not cap the skew funding rate.
+    pub max_rate_per_hour: Option<u32>,
+    /// The precision of the cumulative funding indices, on top of the funding rate precision,
+    /// left unchanged when not given.
+    pub funding_index_precision: Option<u32>,
+    /// Whether the side with the larger open interest pays funding to the other side, left
+    /// unchanged when not given.
+    pub has_skew_funding: Option<bool>,
+    /// The skew funding rate per hour when all of the open interest is on one side, left
+    /// unchanged when not given.
+    pub skew_funding_rate: Option<u32>,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateTokenWeightArgs {
+    /// The target weight of the vault in the pool, relative to the sum of the weights of all vaults.
+    pub weight: u64,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateVaultParamsArgs {
+    /// Whether positions can only be entered or increased through a position request, left
+    /// unchanged when not given.
+    pub has_position_requests: Option<bool>,
+    /// The maximum long open interest, denominated in USD, zero when it is not capped, left
+    /// unchanged when not given.
+    pub max_long_open_interest: Option<u64>,
+    /// The maximum short open interest, denominated in USD, zero when it is not capped, left
+    /// unchanged when not given.
+    pub max_short_open_interest: Option<u64>,
+    /// The maximum deviation between the prices of the oracle and of the secondary oracle,
+    /// represented in basis points, zero when it is not checked, left unchanged when not given.
+    pub max_oracle_deviation_bps: Option<u64>,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct WithdrawLiquidityArgs {
+    /// The amount of LP tokens to burn.
+    pub lp_amount: u64,
+    /// The minimum amount of tokens to receive.
+    pub min_amount_out: u64,
+}
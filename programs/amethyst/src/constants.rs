@@ -1,11 +1,3 @@
-/// The decimals of a pyth price feed.
-pub const PYTH_FEED_DECIMALS: i32 = 0;
-
-/// The current exponent of a switchboard price feed.
-pub const SWITCHBOARD_FEED_EXPONENT: i32 = 0;
-/// The decimals of a switchboard price feed.
-pub const SWITCHBOARD_FEED_DECIMALS: i32 = 0;
-
 /// The target exponent for oracle price.
 /// A large exponent is used so we can easily retain accuracy when consuming price feeds
 /// where the value of the underlying is very small, e.g BONK.
@@ -28,15 +20,60 @@ pub const SECONDS_IN_HOUR: u64 = 3600;
 /// The basis points divisor.
 pub const BASIS_POINTS_DIVISOR: u64 = 10_000;
 
+/// The precision of funding rates and of the funding indices positions keep track of.
+pub const FUNDING_RATE_PRECISION: u64 = 1_000_000;
+
+/// The default precision of the cumulative funding indices, on top of the funding rate precision.
+pub const DEFAULT_FUNDING_INDEX_PRECISION: u32 = 1_000_000;
+/// The default maximum funding rate per hour.
+pub const DEFAULT_MAX_FUNDING_RATE_PER_HOUR: u32 = 10_000;
+
+/// The default funding rate per hour at zero utilisation for non-stable vaults.
+pub const DEFAULT_FUNDING_BASE_RATE: u32 = 0;
+/// The default funding rate per hour added below the kink for non-stable vaults.
+pub const DEFAULT_FUNDING_SLOPE_BELOW_KINK: u32 = 100;
+/// The default utilisation of the kink for non-stable vaults, represented in basis points.
+pub const DEFAULT_FUNDING_KINK_UTILISATION_BPS: u16 = 8_000;
+/// The default funding rate per hour added above the kink for non-stable vaults.
+pub const DEFAULT_FUNDING_SLOPE_ABOVE_KINK: u32 = 1_000;
+
+/// The default funding rate per hour at zero utilisation for stable vaults.
+pub const DEFAULT_STABLE_FUNDING_BASE_RATE: u32 = 0;
+/// The default funding rate per hour added below the kink for stable vaults.
+pub const DEFAULT_STABLE_FUNDING_SLOPE_BELOW_KINK: u32 = 60;
+/// The default utilisation of the kink for stable vaults, represented in basis points.
+pub const DEFAULT_STABLE_FUNDING_KINK_UTILISATION_BPS: u16 = 9_000;
+/// The default funding rate per hour added above the kink for stable vaults.
+pub const DEFAULT_STABLE_FUNDING_SLOPE_ABOVE_KINK: u32 = 600;
+
 /// The time to live for an oracle price, in slots.
 pub const ORACLE_PRICE_TTL_SLOTS: u64 = 30;
 
 /// The time to live for the price coming from an oracle feed, in seconds.
 pub const ORACLE_PRICE_FEED_TTL_SECS: u64 = 30;
 
+/// The number of slots after which a [`PositionRequest`] that was not executed can be cancelled.
+pub const POSITION_REQUEST_TIMEOUT_SLOTS: u64 = 150;
+
+/// The maximum number of vaults that can be registered in the [`Cache`].
+///
+/// Depositing and withdrawing liquidity take four accounts per registered vault to value the pool,
+/// which along with their own accounts must fit within the 64 accounts a transaction can lock.
+pub const MAX_VAULTS: usize = 12;
+
 /// The [`Position`] seed.
 pub const B_POSITION: &[u8] = b"POSITION";
 /// The [`Position`]'s escrow seed.
 pub const B_ESCROW: &[u8] = b"ESCROW";
 /// The [`Position's`] escrow token account seed.
 pub const B_ESCROW_TOKEN_ACCOUNT: &[u8] = b"ESCROW_TOKEN_ACCOUNT";
+/// The [`Config`] seed.
+pub const B_CONFIG: &[u8] = b"CONFIG";
+/// The [`Cache`]'s signer seed.
+pub const B_CACHE_SIGNER: &[u8] = b"CACHE_SIGNER";
+/// The [`Order`] seed.
+pub const B_ORDER: &[u8] = b"ORDER";
+/// The [`IncreaseOrder`] seed.
+pub const B_INCREASE_ORDER: &[u8] = b"INCREASE_ORDER";
+/// The [`PositionRequest`] seed.
+pub const B_POSITION_REQUEST: &[u8] = b"POSITION_REQUEST";
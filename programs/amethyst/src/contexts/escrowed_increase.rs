@@ -0,0 +1,227 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{close_account, transfer, CloseAccount, Token, TokenAccount, Transfer};
+
+use crate::{
+    error::ErrorCode,
+    state::{
+        position::{Direction, Position},
+        vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::{increase_position, open_position, IncreaseResult},
+};
+
+use super::PositionEscrowContext;
+
+/// The context of executing an increase of a [`Position`] whose collateral is escrowed by an
+/// account until a keeper executes it, which is either an increase order or a position request.
+pub trait EscrowedIncreaseContext<'info>: PositionEscrowContext<'info> {
+    fn increase_authority(&self) -> Pubkey;
+    fn increase_index_token_mint(&self) -> Pubkey;
+    fn increase_direction(&self) -> Direction;
+    fn increase_size(&self) -> u64;
+    fn increase_signer(&self) -> AccountInfo<'info>;
+    fn increase_signer_seeds(&self) -> [&[u8]; 5];
+    fn increase_token_account(&self) -> &Account<'info, TokenAccount>;
+    fn increase_rent_receiver(&self) -> &AccountInfo<'info>;
+    /// The vault and the index vault's cache are the accounts changed by the increase, along with
+    /// the [`Position`], while the config sets its margin fee.
+    fn increase_accounts_mut(
+        &mut self,
+    ) -> (
+        &Account<'info, Config>,
+        &mut Account<'info, Vault>,
+        &mut Account<'info, VaultCache>,
+        &mut Account<'info, Position>,
+    );
+
+    /// Validates that an open [`Position`] has the direction of the increase.
+    fn validate_increase_direction(&self) -> Result<()> {
+        let position = self.position();
+        require!(
+            position.size == 0 || position.direction == self.increase_direction(),
+            ErrorCode::PositionDirectionMismatch
+        );
+        Ok(())
+    }
+
+    /// Executes the increase, returning its outcome and the index vault's entry price.
+    ///
+    /// The escrowed collateral is moved into the position's escrow, after which the position is
+    /// opened like in `enter_position`, or increased like in `increase_position` if it already
+    /// exists, in which case the margin fee is paid out of the collateral.
+    fn execute_increase(
+        &mut self,
+        clock: &Clock,
+        escrow_bump: u8,
+    ) -> Result<(IncreaseResult, u64)> {
+        let is_opening = self.position().size == 0;
+        if is_opening {
+            let position_address = self.position().key();
+            let authority = self.increase_authority();
+            let token_mint = self.vault().token_mint;
+            let index_token_mint = self.increase_index_token_mint();
+            let direction = self.increase_direction();
+            self.position_mut().init(
+                position_address,
+                authority,
+                token_mint,
+                index_token_mint,
+                escrow_bump,
+                direction,
+                clock.slot,
+            );
+        }
+        // settle the accrued funding first, which also updates the funding rates of the vaults
+        self.settle_funding(clock)?;
+
+        let collateral = self.increase_token_account().amount;
+        let size = self.increase_size();
+        release_escrowed_collateral(
+            self.token_program(),
+            self.increase_token_account(),
+            self.increase_signer(),
+            &self.increase_signer_seeds(),
+            self.position_vault().to_account_info(),
+            self.increase_rent_receiver().clone(),
+        )?;
+        self.position_vault_mut().reload()?;
+
+        let price = self
+            .index_vault_cache()
+            .get_entry_price(self.position().direction);
+        let collateral_price = self.vault_cache().oracle_price;
+        let token_vault_amount = self.token_vault().amount;
+        let (config, vault, index_vault_cache, position) = self.increase_accounts_mut();
+        let result = if is_opening {
+            open_position(
+                vault,
+                token_vault_amount,
+                index_vault_cache,
+                position,
+                collateral,
+                size,
+                collateral_price,
+            )?;
+            IncreaseResult {
+                collateral_delta_usd: position.collateral.into(),
+                ..Default::default()
+            }
+        } else {
+            increase_position(
+                config,
+                vault,
+                token_vault_amount,
+                index_vault_cache,
+                position,
+                collateral,
+                size,
+                collateral_price,
+            )?
+        };
+        // collect the margin fee out of the collateral
+        self.release_collateral(result.fee_token_amount)?;
+
+        Ok((result, price))
+    }
+
+    /// Validates that the increase does not exceed the maximum allowed leverage for the vault.
+    fn validate_increase_leverage(&self) -> Result<()> {
+        let leverage = self.position().leverage();
+        require!(
+            leverage < self.vault().max_leverage.into(),
+            ErrorCode::PositionLeverageExceedsLimit
+        );
+        Ok(())
+    }
+}
+
+/// Transfers all the collateral escrowed for an increase of a [`Position`] to the given token
+/// account and closes the escrowing token account, whose rent goes to the given receiver.
+pub fn release_escrowed_collateral<'info>(
+    token_program: &Program<'info, Token>,
+    token_account: &Account<'info, TokenAccount>,
+    signer: AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    to: AccountInfo<'info>,
+    rent_receiver: AccountInfo<'info>,
+) -> Result<()> {
+    let cpi_seeds = &[signer_seeds];
+    let cpi_program = token_program.to_account_info();
+
+    let cpi_accounts = Transfer {
+        from: token_account.to_account_info(),
+        to,
+        authority: signer.clone(),
+    };
+    transfer(
+        CpiContext::new_with_signer(cpi_program.clone(), cpi_accounts, cpi_seeds),
+        token_account.amount,
+    )?;
+
+    let cpi_accounts = CloseAccount {
+        account: token_account.to_account_info(),
+        destination: rent_receiver,
+        authority: signer,
+    };
+    close_account(CpiContext::new_with_signer(
+        cpi_program,
+        cpi_accounts,
+        cpi_seeds,
+    ))
+}
+
+macro_rules! impl_escrowed_increase_ctx {
+    ($struct:ty, $increase:ident, $token_account:ident, $rent_receiver:ident) => {
+        $crate::contexts::impl_position_escrow_ctx! { $struct }
+
+        impl<'info> EscrowedIncreaseContext<'info> for $struct {
+            fn increase_authority(&self) -> Pubkey {
+                self.$increase.authority
+            }
+            fn increase_index_token_mint(&self) -> Pubkey {
+                self.$increase.index_token_mint
+            }
+            fn increase_direction(&self) -> Direction {
+                self.$increase.direction
+            }
+            fn increase_size(&self) -> u64 {
+                self.$increase.size
+            }
+            fn increase_signer(&self) -> AccountInfo<'info> {
+                $crate::utils::with_signer_pda(&*self.$increase)
+            }
+            fn increase_signer_seeds(&self) -> [&[u8]; 5] {
+                self.$increase.signer_seeds()
+            }
+            fn increase_token_account(&self) -> &Account<'info, TokenAccount> {
+                &self.$token_account
+            }
+            fn increase_rent_receiver(&self) -> &AccountInfo<'info> {
+                &self.$rent_receiver
+            }
+            fn increase_accounts_mut(
+                &mut self,
+            ) -> (
+                &Account<'info, Config>,
+                &mut Account<'info, Vault>,
+                &mut Account<'info, VaultCache>,
+                &mut Account<'info, Position>,
+            ) {
+                let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+                    &mut self.vault_cache
+                } else {
+                    &mut self.index_vault_cache
+                };
+                (
+                    &self.config,
+                    &mut self.vault,
+                    index_vault_cache,
+                    &mut self.position,
+                )
+            }
+        }
+    };
+}
+
+pub(crate) use impl_escrowed_increase_ctx;
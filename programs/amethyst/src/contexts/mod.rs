@@ -1,3 +1,7 @@
 pub mod change_position;
+pub mod escrowed_increase;
+pub mod position_escrow;
 
 pub use change_position::*;
+pub use escrowed_increase::*;
+pub use position_escrow::*;
@@ -0,0 +1,357 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{close_account, transfer, CloseAccount, Token, TokenAccount, Transfer};
+
+use crate::{
+    error::ErrorCode,
+    events::FundingPaid,
+    state::{
+        position::{Direction, Position},
+        vault::{Vault, VaultCache},
+    },
+    utils::{
+        get_funding_fee, get_skew_funding_fee, refresh_oracle_price, token_to_usd, update_funding,
+        usd_to_token, with_signer_pda, DecreaseResult,
+    },
+};
+
+pub trait PositionEscrowContext<'info> {
+    fn vault_cache(&self) -> &Account<'info, VaultCache>;
+    fn vault(&self) -> &Account<'info, Vault>;
+    fn vault_mut(&mut self) -> &mut Account<'info, Vault>;
+    fn vault_accounts_mut(
+        &mut self,
+    ) -> (&mut Account<'info, Vault>, &mut Account<'info, VaultCache>);
+    /// The index vault's accounts are the vault's own when the position is collateralised with its
+    /// index token, since the same accounts are then passed twice and only the vault's are kept.
+    fn index_vault_cache(&self) -> &Account<'info, VaultCache>;
+    fn index_vault(&self) -> &Account<'info, Vault>;
+    fn index_vault_accounts_mut(
+        &mut self,
+    ) -> (&mut Account<'info, Vault>, &mut Account<'info, VaultCache>);
+    fn token_vault(&self) -> &Account<'info, TokenAccount>;
+    fn token_vault_mut(&mut self) -> &mut Account<'info, TokenAccount>;
+    fn vault_signer(&self) -> &AccountInfo<'info>;
+    fn oracle(&self) -> &AccountInfo<'info>;
+    fn secondary_oracle(&self) -> &AccountInfo<'info>;
+    fn index_oracle(&self) -> &AccountInfo<'info>;
+    fn index_secondary_oracle(&self) -> &AccountInfo<'info>;
+    fn position(&self) -> &Account<'info, Position>;
+    fn position_mut(&mut self) -> &mut Account<'info, Position>;
+    fn escrow(&self) -> &AccountInfo<'info>;
+    fn position_vault(&self) -> &Account<'info, TokenAccount>;
+    fn position_vault_mut(&mut self) -> &mut Account<'info, TokenAccount>;
+    fn token_program(&self) -> &Program<'info, Token>;
+
+    /// Refreshes the oracle prices of the vault and of the index vault from their bound oracle
+    /// accounts, so that the [`Position`] is never changed at a price the trader picked.
+    fn refresh_oracle_prices(&mut self, clock: &Clock) -> Result<()> {
+        let oracle = self.oracle().clone();
+        let secondary_oracle = self.secondary_oracle().clone();
+        let (_, vault_cache) = self.vault_accounts_mut();
+        refresh_oracle_price(vault_cache, &oracle, &secondary_oracle, clock)?;
+
+        if self.index_vault().key() != self.vault().key() {
+            let index_oracle = self.index_oracle().clone();
+            let index_secondary_oracle = self.index_secondary_oracle().clone();
+            let (_, index_vault_cache) = self.index_vault_accounts_mut();
+            refresh_oracle_price(
+                index_vault_cache,
+                &index_oracle,
+                &index_secondary_oracle,
+                clock,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Settles the funding accrued by the [`Position`] since its last payment, failing when its
+    /// collateral is insufficient, in which case the position can only be liquidated.
+    fn settle_funding(&mut self, clock: &Clock) -> Result<()> {
+        require!(
+            self.try_settle_funding(clock)?,
+            ErrorCode::InsufficientCollateralForFunding
+        );
+        Ok(())
+    }
+
+    /// Tries to settle the funding accrued by the [`Position`] since its last payment, returning
+    /// whether it was settled.
+    ///
+    /// The funding rate of the collateral vault and the skew funding indices of the index vault are
+    /// updated and the accrued funding is paid out of the collateral. If the collateral is
+    /// insufficient nothing is paid and the position is instead flagged as liquidatable. When the
+    /// skew funding received outweighs the funding owed, the difference is moved from the vault
+    /// into the escrow and added to the collateral, up to the vault's available liquidity so that
+    /// reserved tokens are never paid out, the rest being forfeited.
+    fn try_settle_funding(&mut self, clock: &Clock) -> Result<bool> {
+        let unix_timestamp: u64 = clock
+            .unix_timestamp
+            .try_into()
+            .or(Err(ErrorCode::InvalidTimestampConversion))?;
+        let (vault, vault_cache) = self.vault_accounts_mut();
+        update_funding(vault, vault_cache, clock)?;
+        if self.index_vault().key() != self.vault().key() {
+            let (index_vault, index_vault_cache) = self.index_vault_accounts_mut();
+            update_funding(index_vault, index_vault_cache, clock)?;
+        }
+
+        let vault = self.vault();
+        let position = self.position();
+        let funding_index = vault.get_funding_index();
+        let skew_funding_index = self.index_vault_cache().get_skew_funding_index(
+            position.direction,
+            self.index_vault().get_funding_index_precision(),
+        );
+        let mut amount = get_funding_fee(vault, position.size, position.last_funding_index) as i128
+            + get_skew_funding_fee(
+                position.size,
+                skew_funding_index,
+                position.last_skew_funding_index,
+            );
+        let mut usd_amount: u64 = amount
+            .unsigned_abs()
+            .try_into()
+            .or(Err(ErrorCode::InvalidTokenAmount))?;
+        let mut token_amount: u64 = usd_to_token(
+            usd_amount.into(),
+            self.vault_cache().oracle_price,
+            vault.decimals,
+        )?
+        .try_into()
+        .or(Err(ErrorCode::InvalidTokenAmount))?;
+
+        if amount >= 0 {
+            if usd_amount > position.collateral || token_amount > self.position_vault().amount {
+                msg!("Insufficient collateral to pay funding, flagging position as liquidatable.");
+                self.position_mut().is_liquidatable = true;
+                return Ok(false);
+            }
+            self.pay_from_collateral(usd_amount, token_amount)?;
+        } else {
+            let available_liquidity = self
+                .vault()
+                .get_available_liquidity(self.token_vault().amount);
+            if token_amount as u128 > available_liquidity {
+                msg!("Insufficient liquidity to pay funding, capping it.");
+                token_amount = available_liquidity as u64;
+                usd_amount = token_to_usd(
+                    token_amount.into(),
+                    self.vault_cache().oracle_price,
+                    self.vault().decimals,
+                )?
+                .try_into()
+                .or(Err(ErrorCode::InvalidTokenAmount))?;
+                amount = -(usd_amount as i128);
+            }
+            if token_amount > 0 {
+                self.pay_out(self.position_vault(), token_amount)?;
+                self.token_vault_mut().reload()?;
+                self.position_vault_mut().reload()?;
+            }
+            match self.position().direction {
+                Direction::Long => {
+                    let guaranteed_usd_delta =
+                        (usd_amount as u128).min(self.vault().guaranteed_usd);
+                    self.vault_mut()
+                        .decrease_guaranteed_usd(guaranteed_usd_delta)?;
+                }
+                Direction::Short => {
+                    self.vault_mut().decrease_pool_amount(token_amount.into())?;
+                }
+            }
+            self.position_mut().collateral += usd_amount;
+        }
+
+        let position = self.position_mut();
+        position.pay_funding(funding_index, skew_funding_index, unix_timestamp);
+
+        emit!(FundingPaid {
+            position: position.key(),
+            authority: position.authority,
+            amount: amount as i64,
+            token_amount: if amount >= 0 {
+                token_amount as i64
+            } else {
+                -(token_amount as i64)
+            },
+            funding_index,
+            skew_funding_index,
+        });
+
+        Ok(true)
+    }
+
+    /// Pays an amount out of the [`Position`]'s collateral into the vault, given both in USD
+    /// and in native token amount.
+    fn pay_from_collateral(&mut self, amount: u64, token_amount: u64) -> Result<()> {
+        if token_amount > 0 {
+            self.release_collateral(token_amount)?;
+            self.position_vault_mut().reload()?;
+        }
+        match self.position().direction {
+            Direction::Long => {
+                // the guaranteed USD tracks the size of long positions net of their collateral
+                self.vault_mut().increase_guaranteed_usd(amount.into());
+            }
+            Direction::Short => {
+                // unlike for longs, the collateral of short positions is not part of the pool yet
+                self.vault_mut().increase_pool_amount(token_amount.into());
+            }
+        }
+        self.position_mut().collateral -= amount;
+        Ok(())
+    }
+
+    /// Transfers collateral from the [`Position`]'s escrow to the given token account.
+    fn transfer_collateral(&self, to: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let cpi_signer = with_signer_pda(self.escrow());
+        let cpi_seeds = &[&self.position().escrow_signer_seeds()[..]];
+        let cpi_program = self.token_program().to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.position_vault().to_account_info(),
+            to: to.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Releases collateral from the [`Position`]'s escrow into the vault.
+    fn release_collateral(&self, amount: u64) -> Result<()> {
+        self.transfer_collateral(self.token_vault(), amount)
+    }
+
+    /// Pays out tokens from the vault to the given token account.
+    fn pay_out(&self, to: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let cpi_signer = with_signer_pda(self.vault_signer());
+        let cpi_seeds = &[&self.vault().vault_signer_seeds()[..]];
+        let cpi_program = self.token_program().to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.token_vault().to_account_info(),
+            to: to.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Settles the decrease of a [`Position`].
+    ///
+    /// The payout is taken out of the released collateral first and out of the vault for the rest,
+    /// while the released collateral that is not paid out goes to the vault.
+    fn settle_decrease(
+        &self,
+        result: &DecreaseResult,
+        receiver: &Account<'info, TokenAccount>,
+    ) -> Result<()> {
+        let collateral_out = result.released_collateral.min(result.payout);
+        self.transfer_collateral(receiver, collateral_out)?;
+        self.release_collateral(result.released_collateral - collateral_out)?;
+        self.pay_out(receiver, result.payout - collateral_out)
+    }
+
+    /// Closes the [`Position`]'s escrow token account, its rent is returned to the destination.
+    fn close_escrow(&self, destination: &AccountInfo<'info>) -> Result<()> {
+        let cpi_signer = with_signer_pda(self.escrow());
+        let cpi_seeds = &[&self.position().escrow_signer_seeds()[..]];
+        let cpi_program = self.token_program().to_account_info();
+        let cpi_accounts = CloseAccount {
+            account: self.position_vault().to_account_info(),
+            destination: destination.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        close_account(cpi_ctx)
+    }
+}
+
+macro_rules! impl_position_escrow_ctx {
+    ($struct:ty) => {
+        impl<'info> PositionEscrowContext<'info> for $struct {
+            fn vault_cache(&self) -> &Account<'info, VaultCache> {
+                &self.vault_cache
+            }
+            fn vault(&self) -> &Account<'info, Vault> {
+                &self.vault
+            }
+            fn vault_mut(&mut self) -> &mut Account<'info, Vault> {
+                &mut self.vault
+            }
+            fn vault_accounts_mut(
+                &mut self,
+            ) -> (&mut Account<'info, Vault>, &mut Account<'info, VaultCache>) {
+                (&mut self.vault, &mut self.vault_cache)
+            }
+            fn index_vault_cache(&self) -> &Account<'info, VaultCache> {
+                if self.index_vault.key() == self.vault.key() {
+                    &self.vault_cache
+                } else {
+                    &self.index_vault_cache
+                }
+            }
+            fn index_vault(&self) -> &Account<'info, Vault> {
+                if self.index_vault.key() == self.vault.key() {
+                    &self.vault
+                } else {
+                    &self.index_vault
+                }
+            }
+            fn index_vault_accounts_mut(
+                &mut self,
+            ) -> (&mut Account<'info, Vault>, &mut Account<'info, VaultCache>) {
+                if self.index_vault.key() == self.vault.key() {
+                    (&mut self.vault, &mut self.vault_cache)
+                } else {
+                    (&mut self.index_vault, &mut self.index_vault_cache)
+                }
+            }
+            fn token_vault(&self) -> &Account<'info, TokenAccount> {
+                &self.token_vault
+            }
+            fn token_vault_mut(&mut self) -> &mut Account<'info, TokenAccount> {
+                &mut self.token_vault
+            }
+            fn vault_signer(&self) -> &AccountInfo<'info> {
+                &self.vault_signer
+            }
+            fn oracle(&self) -> &AccountInfo<'info> {
+                &self.oracle
+            }
+            fn secondary_oracle(&self) -> &AccountInfo<'info> {
+                &self.secondary_oracle
+            }
+            fn index_oracle(&self) -> &AccountInfo<'info> {
+                &self.index_oracle
+            }
+            fn index_secondary_oracle(&self) -> &AccountInfo<'info> {
+                &self.index_secondary_oracle
+            }
+            fn position(&self) -> &Account<'info, Position> {
+                &self.position
+            }
+            fn position_mut(&mut self) -> &mut Account<'info, Position> {
+                &mut self.position
+            }
+            fn escrow(&self) -> &AccountInfo<'info> {
+                &self.escrow
+            }
+            fn position_vault(&self) -> &Account<'info, TokenAccount> {
+                &self.position_vault
+            }
+            fn position_vault_mut(&mut self) -> &mut Account<'info, TokenAccount> {
+                &mut self.position_vault
+            }
+            fn token_program(&self) -> &Program<'info, Token> {
+                &self.token_program
+            }
+        }
+    };
+}
+
+pub(crate) use impl_position_escrow_ctx;
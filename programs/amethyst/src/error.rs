@@ -44,4 +44,88 @@ pub enum ErrorCode {
 
     #[msg("The given oracle price feed is stale.")]
     StaleOracleFeed,
+
+    #[msg("The given vault accounts do not match the vaults registered in the pool.")]
+    InvalidVaultAccounts,
+
+    #[msg("The resulting amount is lower than the minimum amount out.")]
+    SlippageToleranceExceeded,
+
+    #[msg("The redemption would withdraw liquidity reserved for open positions.")]
+    CannotWithdrawReservedLiquidity,
+
+    #[msg("The given vaults cannot be swapped between.")]
+    InvalidSwapVaults,
+
+    #[msg("The position is not eligible for liquidation.")]
+    PositionNotLiquidatable,
+
+    #[msg("The position does not have sufficient collateral to cover its losses.")]
+    InsufficientCollateralForLoss,
+
+    #[msg("The given funding rate parameters are invalid.")]
+    InvalidFundingParams,
+
+    #[msg("The given oracle price feed is invalid.")]
+    InvalidOracleFeed,
+
+    #[msg("Long positions must be collateralised with their index token.")]
+    InvalidCollateralToken,
+
+    #[msg("The given trigger price is invalid.")]
+    InvalidTriggerPrice,
+
+    #[msg("The order's trigger condition is not met.")]
+    OrderNotTriggered,
+
+    #[msg("The order has expired.")]
+    OrderExpired,
+
+    #[msg("The order's direction does not match the position's direction.")]
+    PositionDirectionMismatch,
+
+    #[msg("The vault requires positions to be entered or increased through a position request.")]
+    PositionRequestRequired,
+
+    #[msg("The oracle price was not published after the position request was made.")]
+    PositionRequestPriceNotUpdated,
+
+    #[msg("The position request has timed out.")]
+    PositionRequestTimedOut,
+
+    #[msg("The position request cannot be cancelled before it times out.")]
+    PositionRequestNotTimedOut,
+
+    #[msg("The maximum open interest of the vault would be exceeded.")]
+    MaxOpenInterestExceeded,
+
+    #[msg("The given oracle account does not match the vault's oracle.")]
+    OracleAccountMismatch,
+
+    #[msg("The oracle price is negative.")]
+    NegativeOraclePrice,
+
+    #[msg("The prices of the oracle and of the secondary oracle deviate too much.")]
+    OracleDeviationExceeded,
+
+    #[msg("The maximum number of vaults in the pool would be exceeded.")]
+    MaxVaultsExceeded,
+
+    #[msg("The liquidation target leverage must be set and below the maximum leverage of every vault.")]
+    InvalidLiquidationTargetLeverage,
+
+    #[msg("The order was created for a previous position at the same address.")]
+    StaleOrder,
+
+    #[msg("The operation overflowed.")]
+    MathOverflow,
+
+    #[msg("The fee exceeds the amount it is charged on.")]
+    InvalidFee,
+
+    #[msg("The position does not have sufficient collateral to pay funding.")]
+    InsufficientCollateralForFunding,
+
+    #[msg("The funding index precision cannot be changed while positions are open.")]
+    CannotChangeFundingIndexPrecisionWithOpenPositions,
 }
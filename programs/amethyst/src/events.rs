@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::state::position::Direction;
+use crate::state::{order::TriggerCondition, position::Direction, vault::OracleType};
 
 #[event]
 pub struct PositionOpened {
@@ -31,3 +31,275 @@ pub struct PositionIncreased {
     /// The oracle price.
     pub price: u64,
 }
+
+#[event]
+pub struct PositionClosed {
+    /// The position itself.
+    pub position: Pubkey,
+    /// The authority of the position.
+    pub authority: Pubkey,
+    /// The position size.
+    pub size: u64,
+    /// Whether the realised PnL is a profit.
+    pub has_profit: bool,
+    /// The realised PnL.
+    pub realised_pnl: u128,
+    /// The fee paid.
+    pub fee: u128,
+    /// The amount of tokens paid out.
+    pub payout: u64,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct FundingPaid {
+    /// The position itself.
+    pub position: Pubkey,
+    /// The authority of the position.
+    pub authority: Pubkey,
+    /// The funding paid, denominated in USD, negative when the position received funding.
+    pub amount: i64,
+    /// The funding paid, in native token amount, negative when the position received funding.
+    pub token_amount: i64,
+    /// The funding index the position has paid up to.
+    pub funding_index: u64,
+    /// The skew funding index of the position's side that it has paid up to.
+    pub skew_funding_index: i128,
+}
+
+#[event]
+pub struct PositionDecreased {
+    /// The position itself.
+    pub position: Pubkey,
+    /// The authority of the position.
+    pub authority: Pubkey,
+    /// The size delta.
+    pub size_delta: u64,
+    /// The collateral delta.
+    pub collateral_delta: u64,
+    /// Whether the realised PnL is a profit.
+    pub has_profit: bool,
+    /// The realised PnL.
+    pub realised_pnl: u128,
+    /// The fee paid.
+    pub fee: u128,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct PositionLiquidated {
+    /// The position itself.
+    pub position: Pubkey,
+    /// The authority of the position.
+    pub authority: Pubkey,
+    /// The position size.
+    pub size: u64,
+    /// The collateral of the position.
+    pub collateral: u64,
+    /// The collateral left after losses and fees.
+    pub remaining_collateral: u64,
+    /// The liquidator.
+    pub liquidator: Pubkey,
+    /// The amount of collateral paid to the liquidator.
+    pub liquidation_fee: u64,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct PositionPartiallyLiquidated {
+    /// The position itself.
+    pub position: Pubkey,
+    /// The authority of the position.
+    pub authority: Pubkey,
+    /// The size closed.
+    pub size_delta: u64,
+    /// The position size left.
+    pub size: u64,
+    /// The collateral left.
+    pub collateral: u64,
+    /// The liquidator.
+    pub liquidator: Pubkey,
+    /// The amount of collateral paid to the liquidator.
+    pub liquidation_fee: u64,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct SwapHopExecuted {
+    /// The vault of the input asset.
+    pub vault_in: Pubkey,
+    /// The vault of the output asset.
+    pub vault_out: Pubkey,
+    /// The amount of the input asset.
+    pub amount_in: u64,
+    /// The amount of the output asset.
+    pub amount_out: u64,
+    /// The fee charged, denominated in basis points.
+    pub fee_bps: u64,
+}
+
+#[event]
+pub struct OrderCreated {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The position the order decreases.
+    pub position: Pubkey,
+    /// The trigger price.
+    pub trigger_price: u64,
+    /// The condition under which the order triggers.
+    pub trigger_condition: TriggerCondition,
+    /// The size to close.
+    pub size_delta: u64,
+    /// The execution fee, in lamports.
+    pub execution_fee: u64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The position the order decreased.
+    pub position: Pubkey,
+}
+
+#[event]
+pub struct OrderExecuted {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The position decreased.
+    pub position: Pubkey,
+    /// The keeper.
+    pub keeper: Pubkey,
+    /// The size closed.
+    pub size_delta: u64,
+    /// Whether the realised PnL is a profit.
+    pub has_profit: bool,
+    /// The realised PnL.
+    pub realised_pnl: u128,
+    /// The fees paid.
+    pub fee: u128,
+    /// The amount of tokens paid out.
+    pub payout: u64,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct IncreaseOrderCreated {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The order's authority.
+    pub authority: Pubkey,
+    /// The escrowed collateral.
+    pub collateral: u64,
+    /// The size to open or increase.
+    pub size: u64,
+    /// The direction of the position.
+    pub direction: Direction,
+    /// The limit price.
+    pub limit_price: u64,
+    /// The expiry timestamp, zero if the order never expires.
+    pub expires_at: u64,
+    /// The execution fee, in lamports.
+    pub execution_fee: u64,
+}
+
+#[event]
+pub struct IncreaseOrderCancelled {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The order's authority.
+    pub authority: Pubkey,
+    /// The refunded collateral.
+    pub collateral: u64,
+}
+
+#[event]
+pub struct IncreaseOrderExecuted {
+    /// The order itself.
+    pub order: Pubkey,
+    /// The position opened or increased.
+    pub position: Pubkey,
+    /// The keeper.
+    pub keeper: Pubkey,
+    /// The collateral added.
+    pub collateral_delta_usd: u128,
+    /// The size added.
+    pub size_delta: u64,
+    /// The fees paid.
+    pub fee: u64,
+    /// The oracle price.
+    pub price: u64,
+}
+
+#[event]
+pub struct PositionRequestCreated {
+    /// The request itself.
+    pub request: Pubkey,
+    /// The request's authority.
+    pub authority: Pubkey,
+    /// The escrowed collateral.
+    pub collateral: u64,
+    /// The size to open or increase.
+    pub size: u64,
+    /// The direction of the position.
+    pub direction: Direction,
+    /// The slot at which the request was made.
+    pub slot: u64,
+    /// The execution fee, in lamports.
+    pub execution_fee: u64,
+}
+
+#[event]
+pub struct PositionRequestCancelled {
+    /// The request itself.
+    pub request: Pubkey,
+    /// The request's authority.
+    pub authority: Pubkey,
+    /// The refunded collateral.
+    pub collateral: u64,
+}
+
+#[event]
+pub struct PositionRequestExecuted {
+    /// The request itself.
+    pub request: Pubkey,
+    /// The position opened or increased.
+    pub position: Pubkey,
+    /// The keeper.
+    pub keeper: Pubkey,
+    /// The collateral added.
+    pub collateral_delta_usd: u128,
+    /// The size added.
+    pub size_delta: u64,
+    /// The fees paid.
+    pub fee: u64,
+    /// The oracle price.
+    pub price: u64,
+    /// The slot at which the oracle price was published.
+    pub price_slot: u64,
+}
+
+#[event]
+pub struct OraclePriceUpdated {
+    /// The vault.
+    pub vault: Pubkey,
+    /// The oracle account the price was read from.
+    pub oracle: Pubkey,
+    /// The type of the oracle the price was read from.
+    pub oracle_type: OracleType,
+    /// Whether the price was read from the secondary oracle.
+    pub is_secondary: bool,
+    /// The oracle price.
+    pub price: u64,
+    /// The lower bound of the oracle price.
+    pub min_price: u64,
+    /// The higher bound of the oracle price.
+    pub max_price: u64,
+    /// The slot at which the oracle price was published.
+    pub price_slot: u64,
+}
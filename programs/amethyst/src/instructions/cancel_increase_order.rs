@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    constants::B_ESCROW_TOKEN_ACCOUNT, contexts::release_escrowed_collateral,
+    events::IncreaseOrderCancelled, state::order::IncreaseOrder, utils::with_signer_pda,
+};
+
+#[derive(Accounts)]
+pub struct CancelIncreaseOrder<'info> {
+    /// The order.
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = token_mint,
+        close = authority
+    )]
+    pub order: Box<Account<'info, IncreaseOrder>>,
+
+    /// The order's token account, which escrows the collateral.
+    #[account(
+        mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            order.key().as_ref()
+        ],
+        bump
+    )]
+    pub order_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account, which is refunded the collateral.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = authority,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The order's authority, which is refunded the execution fee and rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelIncreaseOrder<'info> {
+    /// Cancel the order.
+    ///
+    /// The escrowed collateral is refunded and the order's token account is closed.
+    fn process(&self) -> Result<()> {
+        let collateral = self.order_token_account.amount;
+        release_escrowed_collateral(
+            &self.token_program,
+            &self.order_token_account,
+            with_signer_pda(&*self.order),
+            &self.order.signer_seeds(),
+            self.user_token_account.to_account_info(),
+            self.authority.to_account_info(),
+        )?;
+
+        emit!(IncreaseOrderCancelled {
+            order: self.order.key(),
+            authority: self.authority.key(),
+            collateral,
+        });
+
+        Ok(())
+    }
+}
+
+/// Cancelling an order refunds its collateral, whether or not it has expired.
+pub fn handler(ctx: Context<CancelIncreaseOrder>) -> Result<()> {
+    ctx.accounts.process()
+}
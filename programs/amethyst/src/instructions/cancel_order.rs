@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::{events::OrderCancelled, state::order::Order};
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    /// The order.
+    #[account(
+        mut,
+        has_one = authority,
+        close = authority
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    /// The order's authority, which is refunded the execution fee and rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Cancelling an order closes it, which does not require its position to still be open.
+pub fn handler(ctx: Context<CancelOrder>) -> Result<()> {
+    emit!(OrderCancelled {
+        order: ctx.accounts.order.key(),
+        position: ctx.accounts.order.position,
+    });
+    Ok(())
+}
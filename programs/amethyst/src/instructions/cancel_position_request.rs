@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    constants::B_ESCROW_TOKEN_ACCOUNT, contexts::release_escrowed_collateral, error::ErrorCode,
+    events::PositionRequestCancelled, state::position_request::PositionRequest,
+    utils::with_signer_pda,
+};
+
+#[derive(Accounts)]
+pub struct CancelPositionRequest<'info> {
+    /// The request.
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = token_mint,
+        close = authority
+    )]
+    pub request: Box<Account<'info, PositionRequest>>,
+
+    /// The request's token account, which escrows the collateral.
+    #[account(
+        mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            request.key().as_ref()
+        ],
+        bump
+    )]
+    pub request_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account, which is refunded the collateral.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = authority,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The request's authority, which is refunded the execution fee and rent.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> CancelPositionRequest<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the request has timed out, otherwise the
+    /// user could cancel it once the next oracle price turns out to be unfavourable.
+    fn validate(&self, clock: &Clock) -> Result<()> {
+        require!(
+            self.request.is_timed_out(clock.slot),
+            ErrorCode::PositionRequestNotTimedOut
+        );
+        Ok(())
+    }
+
+    /// Cancel the request.
+    ///
+    /// The escrowed collateral is refunded and the request's token account is closed.
+    fn process(&self) -> Result<()> {
+        let collateral = self.request_token_account.amount;
+        release_escrowed_collateral(
+            &self.token_program,
+            &self.request_token_account,
+            with_signer_pda(&*self.request),
+            &self.request.signer_seeds(),
+            self.user_token_account.to_account_info(),
+            self.authority.to_account_info(),
+        )?;
+
+        emit!(PositionRequestCancelled {
+            request: self.request.key(),
+            authority: self.authority.key(),
+            collateral,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<CancelPositionRequest>) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.validate(&clock)?;
+    ctx.accounts.process()
+}
@@ -1,36 +1,222 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::state::{position::Position, vault::Vault};
+use crate::{
+    args::DecreasePositionArgs,
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_change_position_ctx, ChangePositionContext, PositionEscrowContext},
+    error::ErrorCode,
+    events::PositionClosed,
+    state::{
+        position::Position,
+        vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::decrease_position,
+};
 
 #[derive(Accounts)]
 pub struct ClosePosition<'info> {
-    #[account(mut)]
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(
+        mut,
+        has_one = token_mint,
+        has_one = token_vault,
+        has_one = vault_signer,
+    )]
     pub vault: Box<Account<'info, Vault>>,
 
+    /// The vault's token account.
+    #[account(
+        mut,
+        token::authority = vault.vault_signer,
+        token::mint = token_mint
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The vault's token account authority.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer: AccountInfo<'info>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The user's position.
     #[account(
         mut,
         has_one = authority,
+        has_one = token_mint,
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
         close = rent_destination
     )]
     pub position: Box<Account<'info, Position>>,
 
+    /// The escrow of the position.
+    /// CHECK: The escrow PDA for the position.
+    #[account(
+        seeds = [
+            B_ESCROW,
+            position.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump = position.escrow_bump_seed[0]
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    /// The position's token account.
+    #[account(
+        mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = escrow
+    )]
+    pub position_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = authority,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
     /// CHECK: We do not need to check this.
+    #[account(mut)]
     pub rent_destination: AccountInfo<'info>,
 
+    /// The position's authority.
     pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
 }
 
 impl<'info> ClosePosition<'info> {
     /// Perform validation.
     ///
-    /// In this specific case we only need to validate that
+    /// In this specific case we only need to validate that the position is open,
+    /// since an empty position has nothing to settle.
     fn validate(&self) -> Result<()> {
+        require!(self.position.size > 0, ErrorCode::InvalidSizeDelta);
+        Ok(())
+    }
+
+    /// Settle the position.
+    ///
+    /// The whole size is decreased, which realises the PnL, charges the fees and funding, releases
+    /// the reserved tokens and pays out the remaining collateral. Once settled, the escrow token
+    /// account is closed.
+    fn process(&mut self, clock: &Clock) -> Result<()> {
+        // settle the accrued funding first, so that we act on the current collateral
+        self.settle_funding(clock)?;
+
+        let size = self.position.size;
+        let collateral_price = self.vault_cache.oracle_price;
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+        let result = decrease_position(
+            &self.config,
+            &mut self.vault,
+            index_vault_cache,
+            &mut self.position,
+            &DecreasePositionArgs {
+                size_delta: size,
+                collateral_delta: None,
+            },
+            collateral_price,
+            self.position_vault.amount,
+        )?;
+
+        self.settle_decrease(&result, &self.user_token_account)?;
+        self.close_escrow(&self.rent_destination)?;
+
+        emit!(PositionClosed {
+            position: self.position.key(),
+            authority: self.authority.key(),
+            size,
+            has_profit: result.has_profit,
+            realised_pnl: result.realised_pnl,
+            fee: result.fee,
+            payout: result.payout,
+            price: self
+                .index_vault_cache()
+                .get_exit_price(self.position.direction),
+        });
+
         Ok(())
     }
 }
 
-/// To close out the position we have to perform PNL logic.
+/// To close out the position we have to settle its PnL, fees and funding, the position is only
+/// closed once it has been fully settled.
 pub fn handler(ctx: Context<ClosePosition>) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
     ctx.accounts.validate()?;
+    ctx.accounts.process(&clock)?;
     Ok(())
 }
+
+impl_change_position_ctx! { ClosePosition<'info> }
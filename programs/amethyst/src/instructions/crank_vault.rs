@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::vault::{Vault, VaultCache},
+    utils::{refresh_oracle_price, update_funding},
+};
+
+#[derive(Accounts)]
+pub struct CrankVault<'info> {
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(mut)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+}
+
+impl<'info> CrankVault<'info> {
+    /// Refresh the vault.
+    ///
+    /// The oracle price is read from the vault's oracle and stored along with the slot at which it
+    /// was published, after which the vault's funding is accrued up to now.
+    fn process(&mut self, clock: &Clock) -> Result<()> {
+        refresh_oracle_price(
+            &mut self.vault_cache,
+            &self.oracle,
+            &self.secondary_oracle,
+            clock,
+        )?;
+        update_funding(&mut self.vault, &mut self.vault_cache, clock)
+    }
+}
+
+/// Anyone can crank a vault, which keeps its oracle price and funding current when there is
+/// no trading activity.
+pub fn handler(ctx: Context<CrankVault>) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.process(&clock)
+}
@@ -0,0 +1,61 @@
+use std::mem::size_of;
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::CreateConfigArgs,
+    constants::B_CONFIG,
+    state::{cache::Cache, Config},
+    utils::validate_liquidation_target_leverage,
+};
+
+#[derive(Accounts)]
+pub struct CreateConfig<'info> {
+    /// The config, of which there is only one.
+    #[account(
+        init,
+        seeds = [B_CONFIG],
+        bump,
+        space = size_of::<Config>() + 8,
+        payer = payer
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache, whose vaults the liquidation target leverage is checked against.
+    #[account(has_one = authority)]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The config's authority.
+    pub authority: Signer<'info>,
+
+    /// The payer of the transaction fee and rent of accounts being initialised.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateConfig<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we need to validate that the liquidation target leverage is below the
+    /// maximum leverage of the vaults already registered in the cache, which are passed as the
+    /// remaining accounts.
+    fn validate(&self, remaining_accounts: &[AccountInfo], args: &CreateConfigArgs) -> Result<()> {
+        validate_liquidation_target_leverage(
+            &self.cache,
+            remaining_accounts,
+            args.liquidation_target_leverage,
+        )
+    }
+
+    /// Create the config.
+    fn process(&mut self, args: &CreateConfigArgs) -> Result<()> {
+        self.config.init(self.authority.key(), args)
+    }
+}
+
+pub fn handler(ctx: Context<CreateConfig>, args: CreateConfigArgs) -> Result<()> {
+    ctx.accounts.validate(ctx.remaining_accounts, &args)?;
+    ctx.accounts.process(&args)
+}
@@ -1,30 +1,67 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token};
 
-use crate::state::cache::Cache;
+use crate::{
+    constants::{B_CACHE_SIGNER, QUOTE_TOKEN_DECIMALS},
+    state::cache::Cache,
+};
 
 #[derive(Accounts)]
 pub struct CreateGlobalCache<'info> {
+    /// The global cache.
     #[account(zero)]
     pub cache: Box<Account<'info, Cache>>,
 
+    /// The global cache's signer, which is the authority of the LP token mint.
+    /// CHECK: The cache signer PDA for the cache.
+    #[account(
+        seeds = [
+            B_CACHE_SIGNER,
+            cache.key().as_ref()
+        ],
+        bump
+    )]
+    pub cache_signer: AccountInfo<'info>,
+
+    /// The LP token mint, whose tokens are valued in USD.
+    #[account(
+        init,
+        mint::decimals = QUOTE_TOKEN_DECIMALS as u8,
+        mint::authority = cache_signer,
+        payer = payer
+    )]
+    pub lp_token_mint: Box<Account<'info, Mint>>,
+
+    /// The pool's authority.
     pub authority: Signer<'info>,
 
+    /// The payer of the transaction fee and rent of accounts being initialised.
     #[account(mut)]
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> CreateGlobalCache<'info> {
-    /// Perform validation.
-    ///
+    /// Create the global cache.
     ///
-    fn validate(&self) -> Result<()> {
+    /// The pool starts without vaults, they are registered in the cache as they are created.
+    fn process(&mut self, cache_signer_bump: u8) -> Result<()> {
+        let cache_address = self.cache.key();
+        self.cache.init(
+            cache_address,
+            self.authority.key(),
+            self.cache_signer.key(),
+            self.lp_token_mint.key(),
+            cache_signer_bump,
+        );
         Ok(())
     }
 }
 
 pub fn handler(ctx: Context<CreateGlobalCache>) -> Result<()> {
-    ctx.accounts.validate()?;
-    Ok(())
+    let cache_signer_bump = *ctx.bumps.get("cache_signer").unwrap();
+    ctx.accounts.process(cache_signer_bump)
 }
@@ -0,0 +1,160 @@
+use std::mem::size_of;
+
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::{
+    args::CreateIncreaseOrderArgs,
+    constants::{B_ESCROW_TOKEN_ACCOUNT, B_INCREASE_ORDER},
+    error::ErrorCode,
+    events::IncreaseOrderCreated,
+    state::{order::IncreaseOrder, position::Direction},
+};
+
+#[derive(Accounts)]
+pub struct CreateIncreaseOrder<'info> {
+    /// The order.
+    #[account(
+        init,
+        seeds = [
+            B_INCREASE_ORDER,
+            authority.key().as_ref(),
+            index_token_mint.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump,
+        space = size_of::<IncreaseOrder>() + 8,
+        payer = payer
+    )]
+    pub order: Box<Account<'info, IncreaseOrder>>,
+
+    /// The order's token account, which escrows the collateral.
+    #[account(
+        init,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            order.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = order,
+        payer = payer
+    )]
+    pub order_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = authority,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The position's index asset.
+    pub index_token_mint: Box<Account<'info, Mint>>,
+
+    /// The order's authority.
+    pub authority: Signer<'info>,
+
+    /// The payer of the transaction fee, the execution fee and rent of accounts being initialised.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateIncreaseOrder<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the order escrows collateral for some size
+    /// at a limit price, that it has not already expired and that long positions are collateralised
+    /// with their index token.
+    fn validate(&self, clock: &Clock, args: &CreateIncreaseOrderArgs) -> Result<()> {
+        require!(args.collateral > 0, ErrorCode::InvalidTokenAmount);
+        require!(args.size > 0, ErrorCode::InvalidSizeDelta);
+        require!(args.limit_price > 0, ErrorCode::InvalidTriggerPrice);
+        let unix_timestamp: u64 = clock
+            .unix_timestamp
+            .try_into()
+            .or(Err(ErrorCode::InvalidTimestampConversion))?;
+        require!(
+            args.expires_at == 0 || args.expires_at > unix_timestamp,
+            ErrorCode::OrderExpired
+        );
+        if let Direction::Long = args.direction {
+            require!(
+                self.index_token_mint.key() == self.token_mint.key(),
+                ErrorCode::InvalidCollateralToken
+            );
+        }
+        Ok(())
+    }
+
+    /// Create the order.
+    ///
+    /// The collateral is transferred into the order's token account and the execution fee into the
+    /// order, which pays it to the keeper once executed.
+    fn process(&mut self, clock: &Clock, bump: u8, args: &CreateIncreaseOrderArgs) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = token::Transfer {
+            from: self.user_token_account.to_account_info(),
+            to: self.order_token_account.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), args.collateral)?;
+
+        if args.execution_fee > 0 {
+            let cpi_program = self.system_program.to_account_info();
+            let cpi_accounts = system_program::Transfer {
+                from: self.payer.to_account_info(),
+                to: self.order.to_account_info(),
+            };
+            system_program::transfer(
+                CpiContext::new(cpi_program, cpi_accounts),
+                args.execution_fee,
+            )?;
+        }
+
+        let order_address = self.order.key();
+        let order = &mut self.order;
+        order.init(
+            order_address,
+            self.authority.key(),
+            self.token_mint.key(),
+            self.index_token_mint.key(),
+            bump,
+            args.direction,
+        );
+        order.collateral = args.collateral;
+        order.size = args.size;
+        order.limit_price = args.limit_price;
+        order.expires_at = args.expires_at;
+        order.execution_fee = args.execution_fee;
+        order.slot = clock.slot;
+
+        emit!(IncreaseOrderCreated {
+            order: order_address,
+            authority: self.authority.key(),
+            collateral: args.collateral,
+            size: args.size,
+            direction: args.direction,
+            limit_price: args.limit_price,
+            expires_at: args.expires_at,
+            execution_fee: args.execution_fee,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<CreateIncreaseOrder>, args: CreateIncreaseOrderArgs) -> Result<()> {
+    let clock = Clock::get()?;
+    let bump = *ctx.bumps.get("order").unwrap();
+    ctx.accounts.validate(&clock, &args)?;
+    ctx.accounts.process(&clock, bump, &args)
+}
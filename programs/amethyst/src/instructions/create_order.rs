@@ -0,0 +1,110 @@
+use std::mem::size_of;
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{
+    args::CreateOrderArgs,
+    constants::B_ORDER,
+    error::ErrorCode,
+    events::OrderCreated,
+    state::{order::Order, position::Position},
+};
+
+#[derive(Accounts)]
+#[instruction(args: CreateOrderArgs)]
+pub struct CreateOrder<'info> {
+    /// The user's position.
+    #[account(
+        has_one = authority,
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// The order.
+    #[account(
+        init,
+        seeds = [
+            B_ORDER,
+            position.key().as_ref(),
+            &[args.trigger_condition as u8]
+        ],
+        bump,
+        space = size_of::<Order>() + 8,
+        payer = payer
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    /// The position's authority.
+    pub authority: Signer<'info>,
+
+    /// The payer of the transaction fee, the execution fee and rent of accounts being initialised.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateOrder<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the order has a trigger price
+    /// and that it decreases the position by something.
+    fn validate(&self, args: &CreateOrderArgs) -> Result<()> {
+        require!(args.trigger_price > 0, ErrorCode::InvalidTriggerPrice);
+        require!(
+            args.size_delta > 0 && args.size_delta <= self.position.size,
+            ErrorCode::InvalidSizeDelta
+        );
+        Ok(())
+    }
+
+    /// Create the order.
+    ///
+    /// The execution fee is transferred into the order, which pays it to the keeper once executed.
+    fn process(&mut self, bump: u8, args: &CreateOrderArgs) -> Result<()> {
+        if args.execution_fee > 0 {
+            let cpi_program = self.system_program.to_account_info();
+            let cpi_accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: self.order.to_account_info(),
+            };
+            transfer(
+                CpiContext::new(cpi_program, cpi_accounts),
+                args.execution_fee,
+            )?;
+        }
+
+        let order_address = self.order.key();
+        let order = &mut self.order;
+        order.init(
+            order_address,
+            self.authority.key(),
+            self.position.key(),
+            self.position.opened_slot,
+            bump,
+        );
+        order.trigger_price = args.trigger_price;
+        order.trigger_condition = args.trigger_condition;
+        order.size_delta = args.size_delta;
+        order.execution_fee = args.execution_fee;
+
+        emit!(OrderCreated {
+            order: order_address,
+            position: self.position.key(),
+            trigger_price: args.trigger_price,
+            trigger_condition: args.trigger_condition,
+            size_delta: args.size_delta,
+            execution_fee: args.execution_fee,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<CreateOrder>, args: CreateOrderArgs) -> Result<()> {
+    let bump = *ctx.bumps.get("order").unwrap();
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.process(bump, &args)
+}
@@ -0,0 +1,147 @@
+use std::mem::size_of;
+
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token::{self, Mint, Token, TokenAccount};
+
+use crate::{
+    args::CreatePositionRequestArgs,
+    constants::{B_ESCROW_TOKEN_ACCOUNT, B_POSITION_REQUEST},
+    error::ErrorCode,
+    events::PositionRequestCreated,
+    state::{position::Direction, position_request::PositionRequest},
+};
+
+#[derive(Accounts)]
+pub struct CreatePositionRequest<'info> {
+    /// The request.
+    #[account(
+        init,
+        seeds = [
+            B_POSITION_REQUEST,
+            authority.key().as_ref(),
+            index_token_mint.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump,
+        space = size_of::<PositionRequest>() + 8,
+        payer = payer
+    )]
+    pub request: Box<Account<'info, PositionRequest>>,
+
+    /// The request's token account, which escrows the collateral.
+    #[account(
+        init,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            request.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = request,
+        payer = payer
+    )]
+    pub request_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = authority,
+    )]
+    pub user_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The position's index asset.
+    pub index_token_mint: Box<Account<'info, Mint>>,
+
+    /// The request's authority.
+    pub authority: Signer<'info>,
+
+    /// The payer of the transaction fee, the execution fee and rent of accounts being initialised.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreatePositionRequest<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the request escrows collateral for some size
+    /// and that long positions are collateralised with their index token.
+    fn validate(&self, args: &CreatePositionRequestArgs) -> Result<()> {
+        require!(args.collateral > 0, ErrorCode::InvalidTokenAmount);
+        require!(args.size > 0, ErrorCode::InvalidSizeDelta);
+        if let Direction::Long = args.direction {
+            require!(
+                self.index_token_mint.key() == self.token_mint.key(),
+                ErrorCode::InvalidCollateralToken
+            );
+        }
+        Ok(())
+    }
+
+    /// Create the request.
+    ///
+    /// The collateral is transferred into the request's token account and the execution fee into
+    /// the request, which pays it to the keeper once executed.
+    fn process(&mut self, clock: &Clock, bump: u8, args: &CreatePositionRequestArgs) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = token::Transfer {
+            from: self.user_token_account.to_account_info(),
+            to: self.request_token_account.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        token::transfer(CpiContext::new(cpi_program, cpi_accounts), args.collateral)?;
+
+        if args.execution_fee > 0 {
+            let cpi_program = self.system_program.to_account_info();
+            let cpi_accounts = system_program::Transfer {
+                from: self.payer.to_account_info(),
+                to: self.request.to_account_info(),
+            };
+            system_program::transfer(
+                CpiContext::new(cpi_program, cpi_accounts),
+                args.execution_fee,
+            )?;
+        }
+
+        let request_address = self.request.key();
+        let request = &mut self.request;
+        request.init(
+            request_address,
+            self.authority.key(),
+            self.token_mint.key(),
+            self.index_token_mint.key(),
+            bump,
+            args.direction,
+        );
+        request.collateral = args.collateral;
+        request.size = args.size;
+        request.slot = clock.slot;
+        request.execution_fee = args.execution_fee;
+
+        emit!(PositionRequestCreated {
+            request: request_address,
+            authority: self.authority.key(),
+            collateral: args.collateral,
+            size: args.size,
+            direction: args.direction,
+            slot: clock.slot,
+            execution_fee: args.execution_fee,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<CreatePositionRequest>, args: CreatePositionRequestArgs) -> Result<()> {
+    let clock = Clock::get()?;
+    let bump = *ctx.bumps.get("request").unwrap();
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.process(&clock, bump, &args)
+}
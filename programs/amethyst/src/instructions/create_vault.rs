@@ -1,33 +1,177 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Token;
+use anchor_spl::token::{Mint, Token, TokenAccount};
 
-use crate::state::vault::Vault;
+use crate::{
+    args::CreateVaultArgs,
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    error::ErrorCode,
+    state::{
+        cache::Cache,
+        oracle::{PythOracleInfo, SwitchboardOracleInfo},
+        vault::{OracleType, Vault, VaultCache},
+        Config,
+    },
+    utils::refresh_oracle_price,
+};
 
 #[derive(Accounts)]
 pub struct CreateVault<'info> {
-    #[account(mut)]
-    pub liquidity_pool: Box<Account<'info, Vault>>,
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache, in which the vault is registered.
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The vault of an asset.
+    #[account(zero)]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's cache.
+    #[account(zero)]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault's token account authority.
+    /// CHECK: The vault signer PDA for the vault.
+    #[account(
+        seeds = [
+            B_ESCROW,
+            vault.key().as_ref()
+        ],
+        bump
+    )]
+    pub vault_signer: AccountInfo<'info>,
 
+    /// The vault's token account.
+    #[account(
+        init,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            vault.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = vault_signer,
+        payer = payer
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The vault's asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The vault's oracle account, which is bound to the vault's cache.
+    /// CHECK: The oracle account is checked by reading its price according to the oracle type.
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, of the other oracle type, which is bound to the
+    /// vault's cache, the system program when the vault has no secondary oracle.
+    /// CHECK: The oracle account is checked by reading its price according to the oracle type.
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The vault's authority.
     pub authority: Signer<'info>,
 
+    /// The payer of the transaction fee and rent of accounts being initialised.
     #[account(mut)]
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> CreateVault<'info> {
     /// Perform validation.
     ///
-    ///
-    fn validate(&self) -> Result<()> {
+    /// In this specific case we need to validate that the vault's maximum leverage is above the
+    /// leverage partially liquidated positions are brought back to.
+    fn validate(&self, args: &CreateVaultArgs) -> Result<()> {
+        require!(
+            args.max_leverage > self.config.liquidation_target_leverage,
+            ErrorCode::InvalidLiquidationTargetLeverage
+        );
         Ok(())
     }
+
+    /// Create the vault.
+    ///
+    /// The vault is registered in the global cache, after which the pool's AUM can only be computed
+    /// along with it.
+    ///
+    /// The oracle accounts and their maximum confidence intervals are bound to the vault's cache,
+    /// every price the vault uses from now on is read from those accounts. Reading the initial
+    /// price also validates that the vault has a live feed.
+    fn process(
+        &mut self,
+        clock: &Clock,
+        vault_signer_bump: u8,
+        args: &CreateVaultArgs,
+    ) -> Result<()> {
+        let vault_address = self.vault.key();
+        let vault = &mut self.vault;
+        vault.init(
+            vault_address,
+            self.authority.key(),
+            self.token_mint.key(),
+            self.token_vault.key(),
+            self.vault_signer.key(),
+        );
+        vault.vault_signer_seed_bump = [vault_signer_bump];
+        vault.decimals = self.token_mint.decimals;
+        vault.is_stable = args.is_stable;
+        vault.has_dynamic_fees = args.has_dynamic_fees;
+        vault.max_leverage = args.max_leverage;
+        vault.set_default_funding_params();
+        vault.cache_index = self.cache.register_vault(vault_address)?;
+
+        let vault_cache = &mut self.vault_cache;
+        vault_cache.init(self.authority.key(), vault_address);
+        vault_cache.oracle_type = args.oracle_type;
+        vault_cache.has_secondary_oracle = args.has_secondary_oracle;
+        vault_cache.max_oracle_deviation_bps = args.max_oracle_deviation_bps;
+        bind_oracle(vault_cache, args.oracle_type, self.oracle.key(), args);
+        if args.has_secondary_oracle {
+            let secondary_oracle_type = vault_cache.get_secondary_oracle_type();
+            bind_oracle(
+                vault_cache,
+                secondary_oracle_type,
+                self.secondary_oracle.key(),
+                args,
+            );
+        }
+        refresh_oracle_price(vault_cache, &self.oracle, &self.secondary_oracle, clock)
+    }
+}
+
+/// Binds the oracle account of the given type to the vault's cache.
+fn bind_oracle(
+    vault_cache: &mut VaultCache,
+    oracle_type: OracleType,
+    oracle_account: Pubkey,
+    args: &CreateVaultArgs,
+) {
+    match oracle_type {
+        OracleType::Pyth => {
+            vault_cache.pyth_oracle = PythOracleInfo {
+                price_account: oracle_account,
+                max_confidence_interval: args.pyth_max_confidence_interval,
+            };
+        }
+        OracleType::SwitchboardV2 => {
+            vault_cache.switchboard_oracle = SwitchboardOracleInfo {
+                aggregator_account: oracle_account,
+                max_confidence_interval: args.switchboard_max_confidence_interval,
+            };
+        }
+    }
 }
 
-pub fn handler(ctx: Context<CreateVault>) -> Result<()> {
-    ctx.accounts.validate()?;
-    Ok(())
+pub fn handler(ctx: Context<CreateVault>, args: CreateVaultArgs) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault_signer_bump = *ctx.bumps.get("vault_signer").unwrap();
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.process(&clock, vault_signer_bump, &args)
 }
@@ -1,10 +1,57 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{TokenAccount, Mint, Token};
-
-use crate::{state::{position::Position, Vault, VaultCache}, constants::B_ESCROW, error::ErrorCode};
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    args::DecreasePositionArgs,
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_change_position_ctx, ChangePositionContext, PositionEscrowContext},
+    error::ErrorCode,
+    events::PositionDecreased,
+    state::{
+        position::Position,
+        vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::decrease_position,
+};
 
 #[derive(Accounts)]
 pub struct DecreasePosition<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
     /// The vault's cache.
     #[account(
         mut,
@@ -33,10 +80,26 @@ pub struct DecreasePosition<'info> {
     /// CHECK: The vault signer is checked.
     pub vault_signer: AccountInfo<'info>,
 
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
     /// The user's position.
     #[account(
         mut,
         has_one = authority,
+        has_one = token_mint,
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
     )]
     pub position: Box<Account<'info, Position>>,
 
@@ -55,6 +118,11 @@ pub struct DecreasePosition<'info> {
     /// The position's token account.
     #[account(
         mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
         token::mint = token_mint,
         token::authority = escrow
     )]
@@ -68,7 +136,7 @@ pub struct DecreasePosition<'info> {
     )]
     pub user_token_account: Box<Account<'info, TokenAccount>>,
 
-    /// The position's asset.
+    /// The position's collateral asset.
     pub token_mint: Box<Account<'info, Mint>>,
 
     /// The position's authority.
@@ -82,23 +150,73 @@ pub struct DecreasePosition<'info> {
 }
 
 impl<'info> DecreasePosition<'info> {
-    fn validate(&self) -> Result<()> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the position is decreased by something
+    /// and that it is not closed, which is done by closing the position.
+    fn validate(&self, args: &DecreasePositionArgs) -> Result<()> {
+        require!(
+            args.size_delta < self.position.size,
+            ErrorCode::InvalidSizeDelta
+        );
+        require!(
+            args.size_delta > 0 || args.collateral_delta.unwrap_or_default() > 0,
+            ErrorCode::InvalidSizeDelta
+        );
         Ok(())
     }
 
-    fn process(&self, size: u64) -> Result<()> {
-
-        
+    /// Decrease the position.
+    ///
+    /// The PnL is realised pro-rata to the size delta at the index vault's exit price, the user's
+    /// share is paid out of the escrow first and out of the vault for the rest.
+    fn process(&mut self, clock: &Clock, args: &DecreasePositionArgs) -> Result<()> {
+        // settle the accrued funding first, so that we act on the current collateral
+        self.settle_funding(clock)?;
+
+        let collateral_price = self.vault_cache.oracle_price;
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+        let result = decrease_position(
+            &self.config,
+            &mut self.vault,
+            index_vault_cache,
+            &mut self.position,
+            args,
+            collateral_price,
+            self.position_vault.amount,
+        )?;
+
+        self.settle_decrease(&result, &self.user_token_account)?;
+
+        emit!(PositionDecreased {
+            position: self.position.key(),
+            authority: self.authority.key(),
+            size_delta: args.size_delta,
+            collateral_delta: args.collateral_delta.unwrap_or_default(),
+            has_profit: result.has_profit,
+            realised_pnl: result.realised_pnl,
+            fee: result.fee,
+            price: self
+                .index_vault_cache()
+                .get_exit_price(self.position.direction),
+        });
 
         Ok(())
     }
 
-
     /// Perform validation after performing an action.
     ///
     /// In this specific case we need to validate that, after potentially withdrawing collateral
     /// from the position, we do not exceed the maximum allowed leverage for this vault.
     fn post_validation(&self) -> Result<()> {
+        require!(
+            self.position.collateral > 0,
+            ErrorCode::PositionLeverageExceedsLimit
+        );
         let leverage = self.position.leverage();
         require!(
             leverage < self.vault.max_leverage.into(),
@@ -108,9 +226,13 @@ impl<'info> DecreasePosition<'info> {
     }
 }
 
-pub fn handler(ctx: Context<DecreasePosition>, size: u64) -> Result<()> {
-    ctx.accounts.validate()?;
-    ctx.accounts.process(size)?;
+pub fn handler(ctx: Context<DecreasePosition>, args: DecreasePositionArgs) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.process(&clock, &args)?;
     ctx.accounts.post_validation()?;
     Ok(())
-}
\ No newline at end of file
+}
+
+impl_change_position_ctx! { DecreasePosition<'info> }
@@ -1,10 +1,55 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{mint_to, transfer, Mint, MintTo, Token, TokenAccount, Transfer};
 
-use crate::state::Vault;
+use crate::{
+    args::DepositLiquidityArgs,
+    error::ErrorCode,
+    state::{Cache, Config, Vault, VaultCache},
+    utils::{
+        get_amount_after_fee, get_aum, get_fee_bps, get_lp_mint_amount, load_vaults,
+        refresh_oracle_price, token_to_usd, with_signer_pda,
+    },
+};
 
 #[derive(Accounts)]
 pub struct DepositLiquidity<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache.
+    #[account(
+        mut,
+        has_one = lp_token_mint,
+        has_one = cache_signer,
+    )]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The global cache's signer, which is the authority of the LP token mint.
+    /// CHECK: The cache signer is checked.
+    pub cache_signer: AccountInfo<'info>,
+
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The vault of the deposited asset.
     #[account(
         mut,
         has_one = token_vault,
@@ -12,6 +57,7 @@ pub struct DepositLiquidity<'info> {
     )]
     pub vault: Box<Account<'info, Vault>>,
 
+    /// The vault's token account.
     #[account(
         mut,
         token::authority = vault.vault_signer,
@@ -19,8 +65,10 @@ pub struct DepositLiquidity<'info> {
     )]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
+    /// The deposited asset.
     pub token_mint: Box<Account<'info, Mint>>,
 
+    /// The user's token account.
     #[account(
         mut,
         token::authority = authority,
@@ -28,35 +76,130 @@ pub struct DepositLiquidity<'info> {
     )]
     pub token_account: Box<Account<'info, TokenAccount>>,
 
+    /// The LP token mint.
+    #[account(mut)]
     pub lp_token_mint: Box<Account<'info, Mint>>,
 
+    /// The user's LP token account.
     #[account(
         init_if_needed,
         payer = payer,
         token::authority = authority,
-        token::mint = token_mint
+        token::mint = lp_token_mint
     )]
     pub lp_token_account: Box<Account<'info, TokenAccount>>,
 
+    /// The user's wallet.
     pub authority: Signer<'info>,
 
+    /// The payer of the transaction fee and rent of accounts being initialised.
     #[account(mut)]
     pub payer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
-
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> DepositLiquidity<'info> {
     /// Perform validation.
     ///
+    /// In this specific case we need to validate that the deposit amount is valid and that the
+    /// vault is registered in the pool.
+    fn validate(&self, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidTokenAmount);
+        require!(
+            self.cache
+                .is_registered(self.vault.key(), self.vault.cache_index),
+            ErrorCode::InvalidVaultAccounts
+        );
+        Ok(())
+    }
+
+    /// Transfer the deposit from the user to the vault.
+    fn transfer_deposit(&self, amount: u64) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.token_account.to_account_info(),
+            to: self.token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Mint the LP tokens to the user.
+    fn mint_lp_tokens(&self, amount: u64) -> Result<()> {
+        let cpi_signer = with_signer_pda(&self.cache_signer);
+        let cpi_seeds = &[&self.cache.cache_signer_seeds()[..]];
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = MintTo {
+            mint: self.lp_token_mint.to_account_info(),
+            to: self.lp_token_account.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        mint_to(cpi_ctx, amount)
+    }
+
+    /// Deposit the liquidity.
     ///
-    fn validate(&self) -> Result<()> {
+    /// The deposit is valued at the lower bound of the vault's oracle price and LP tokens are
+    /// minted pro-rata to the pool's maximised AUM. The fee is kept in the vault, which means that
+    /// it accrues to LPs.
+    fn process(&mut self, aum: u128, args: &DepositLiquidityArgs) -> Result<()> {
+        let usd_amount = token_to_usd(
+            args.amount.into(),
+            self.vault_cache.get_price(false),
+            self.vault.decimals,
+        )?;
+        let fee_bps = get_fee_bps(
+            &self.cache,
+            &self.vault,
+            usd_amount,
+            self.config.mint_burn_fee_bps,
+            self.config.tax_bps,
+            true,
+        );
+        let usd_amount_after_fee = get_amount_after_fee(usd_amount, fee_bps)?;
+
+        let lp_amount: u64 =
+            get_lp_mint_amount(usd_amount_after_fee, aum, self.cache.lp_token_supply)
+                .try_into()
+                .or(Err(ErrorCode::InvalidTokenAmount))?;
+        require!(
+            lp_amount >= args.min_lp_out,
+            ErrorCode::SlippageToleranceExceeded
+        );
+
+        self.transfer_deposit(args.amount)?;
+        self.mint_lp_tokens(lp_amount)?;
+
+        self.vault.increase_pool_amount(args.amount.into());
+        self.vault.increase_debt_amount(usd_amount_after_fee);
+        self.cache.increase_lp_token_supply(lp_amount);
+
         Ok(())
     }
 }
 
-pub fn handler(ctx: Context<DepositLiquidity>) -> Result<()> {
-    Ok(())
+/// Deposits liquidity into a vault in exchange for LP tokens.
+///
+/// The remaining accounts must contain every vault registered in the pool, along with their caches
+/// and oracle and secondary oracle accounts, which are used to compute the pool's AUM.
+pub fn handler(ctx: Context<DepositLiquidity>, args: DepositLiquidityArgs) -> Result<()> {
+    ctx.accounts.validate(args.amount)?;
+
+    let clock = Clock::get()?;
+    refresh_oracle_price(
+        &mut ctx.accounts.vault_cache,
+        &ctx.accounts.oracle,
+        &ctx.accounts.secondary_oracle,
+        &clock,
+    )?;
+
+    let vaults = load_vaults(&ctx.accounts.cache, ctx.remaining_accounts, &clock)?;
+    let aum = get_aum(&vaults)?;
+
+    ctx.accounts.process(aum.max, &args)
 }
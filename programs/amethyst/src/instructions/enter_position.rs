@@ -5,17 +5,50 @@ use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::{
     args::EnterPositionArgs,
-    constants::B_POSITION,
-    contexts::{impl_change_position_ctx, ChangePositionContext},
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT, B_POSITION},
+    contexts::{impl_change_position_ctx, ChangePositionContext, PositionEscrowContext},
     error::ErrorCode,
     state::{
         position::{Direction, Position},
         vault::{Vault, VaultCache},
     },
+    utils::{open_position, update_funding},
 };
 
 #[derive(Accounts)]
 pub struct EnterPosition<'info> {
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
     /// The vault's cache.
     #[account(
         mut,
@@ -44,12 +77,27 @@ pub struct EnterPosition<'info> {
     /// CHECK: The vault signer is checked.
     pub vault_signer: AccountInfo<'info>,
 
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
     /// The user's position.
     #[account(
         init,
         seeds = [
             B_POSITION,
             authority.key().as_ref(),
+            index_vault.token_mint.as_ref(),
             token_mint.key().as_ref()
         ],
         bump,
@@ -58,6 +106,32 @@ pub struct EnterPosition<'info> {
     )]
     pub position: Box<Account<'info, Position>>,
 
+    /// The escrow of the position.
+    /// CHECK: The escrow PDA for the position.
+    #[account(
+        seeds = [
+            B_ESCROW,
+            position.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    /// The position's token account.
+    #[account(
+        init,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = escrow,
+        payer = payer
+    )]
+    pub position_vault: Box<Account<'info, TokenAccount>>,
+
     /// The user's token account.
     #[account(
         mut,
@@ -66,7 +140,7 @@ pub struct EnterPosition<'info> {
     )]
     pub user_token_account: Box<Account<'info, TokenAccount>>,
 
-    /// The position's asset.
+    /// The position's collateral asset.
     pub token_mint: Box<Account<'info, Mint>>,
 
     /// The position's authority.
@@ -78,61 +152,97 @@ pub struct EnterPosition<'info> {
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> EnterPosition<'info> {
     /// Perform validation.
     ///
-    /// In this specific case we need to validate that the desired position size
-    /// is not greater than the available liquidity in the vault.
-    fn validate(&self, amount: u64) -> Result<()> {
-        let available_liquidity = self
-            .vault
-            .deposits
-            .checked_sub(self.vault.reserved)
-            .unwrap();
+    /// In this specific case we need to validate that neither vault requires position requests
+    /// and that long positions are collateralised with their index token.
+    fn validate(&self, args: &EnterPositionArgs) -> Result<()> {
         require!(
-            available_liquidity < amount.into(),
-            ErrorCode::InsufficientLiquidityToEnterPosition
+            !self.vault.has_position_requests && !self.index_vault.has_position_requests,
+            ErrorCode::PositionRequestRequired
         );
+        if let Direction::Long = args.direction {
+            require!(
+                self.index_vault.key() == self.vault.key(),
+                ErrorCode::InvalidCollateralToken
+            );
+        }
         Ok(())
     }
 
     /// Enters the position.
     ///
-    /// We do this by transferring the user's collateral to the escrow along with
-    /// the remaining funds from the vault's token account, which are then marked as reserved.
-    fn enter_position(&mut self, args: &EnterPositionArgs) -> Result<()> {
-        let reserved = args.size.checked_sub(args.collateral).unwrap();
+    /// We do this by transferring the user's collateral to the escrow and reserving the
+    /// amount of collateral tokens needed to cover the position size from the vault,
+    /// as long as they are not greater than the available liquidity in the vault,
+    /// while the open interest is tracked by the index vault.
+    fn enter_position(
+        &mut self,
+        clock: &Clock,
+        escrow_bump: u8,
+        args: &EnterPositionArgs,
+    ) -> Result<()> {
+        let unix_timestamp: u64 = clock
+            .unix_timestamp
+            .try_into()
+            .or(Err(ErrorCode::InvalidTimestampConversion))?;
+        let has_index_vault = self.index_vault.key() != self.vault.key();
+        // update the funding rates of the vaults before proceeding
+        update_funding(&mut self.vault, &mut self.vault_cache, clock)?;
+        if has_index_vault {
+            update_funding(&mut self.index_vault, &mut self.index_vault_cache, clock)?;
+        }
 
         self.deposit_collateral(args.collateral)?;
 
+        let collateral_price = self.vault_cache.oracle_price;
+        let position_address = self.position.key();
         let position = &mut self.position;
         let vault = &mut self.vault;
-        let vault_cache = &mut self.vault_cache;
+        let (index_vault, index_vault_cache) = if has_index_vault {
+            (&self.index_vault, &mut self.index_vault_cache)
+        } else {
+            (&*vault, &mut self.vault_cache)
+        };
 
         position.init(
+            position_address,
             self.authority.key(),
             self.token_mint.key(),
-            args.collateral,
-            args.size,
-            0u64,
+            index_vault.token_mint,
+            escrow_bump,
+            args.direction,
+            clock.slot,
+        );
+        position.pay_funding(
+            vault.get_funding_index(),
+            index_vault_cache
+                .get_skew_funding_index(args.direction, index_vault.get_funding_index_precision()),
+            unix_timestamp,
         );
 
-        let _ = match args.direction {
-            Direction::Long => vault_cache.get_next_long_average_entry_price(args.size),
-            Direction::Short => vault_cache.get_next_short_average_entry_price(args.size),
-        };
-
-        vault.increase_reserved(reserved.into());
-
-        Ok(())
+        open_position(
+            vault,
+            self.token_vault.amount,
+            index_vault_cache,
+            position,
+            args.collateral,
+            args.size,
+            collateral_price,
+        )
     }
 }
 
 pub fn handler(ctx: Context<EnterPosition>, args: EnterPositionArgs) -> Result<()> {
-    ctx.accounts.validate(args.size)?;
-    ctx.accounts.enter_position(&args)?;
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
+    let escrow_bump = *ctx.bumps.get("escrow").unwrap();
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.enter_position(&clock, escrow_bump, &args)?;
     Ok(())
 }
 
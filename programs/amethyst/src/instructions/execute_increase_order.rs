@@ -0,0 +1,253 @@
+use std::mem::size_of;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT, B_POSITION},
+    contexts::{impl_escrowed_increase_ctx, EscrowedIncreaseContext, PositionEscrowContext},
+    error::ErrorCode,
+    events::IncreaseOrderExecuted,
+    state::{
+        order::IncreaseOrder,
+        position::{Direction, Position},
+        vault::{Vault, VaultCache},
+        Config,
+    },
+};
+
+#[derive(Accounts)]
+pub struct ExecuteIncreaseOrder<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(
+        mut,
+        has_one = token_mint,
+        has_one = token_vault,
+        has_one = vault_signer,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's token account.
+    #[account(
+        mut,
+        token::authority = vault.vault_signer,
+        token::mint = token_mint
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The vault's token account authority.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer: AccountInfo<'info>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The order, its execution fee and rent go to the keeper.
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = order.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
+        constraint = order.authority == order_authority.key(),
+        close = keeper
+    )]
+    pub order: Box<Account<'info, IncreaseOrder>>,
+
+    /// The order's token account, which escrows the collateral.
+    #[account(
+        mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            order.key().as_ref()
+        ],
+        bump
+    )]
+    pub order_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The position to open or increase, its rent is paid by the keeper when opening it.
+    #[account(
+        init_if_needed,
+        seeds = [
+            B_POSITION,
+            order.authority.as_ref(),
+            index_vault.token_mint.as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump,
+        space = size_of::<Position>() + 8,
+        payer = keeper
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// The escrow of the position.
+    /// CHECK: The escrow PDA for the position.
+    #[account(
+        seeds = [
+            B_ESCROW,
+            position.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    /// The position's token account.
+    #[account(
+        init_if_needed,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = escrow,
+        payer = keeper
+    )]
+    pub position_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The order's authority, which is refunded the rent of the order's token account.
+    /// CHECK: This is checked.
+    #[account(mut)]
+    pub order_authority: AccountInfo<'info>,
+
+    /// The keeper.
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> ExecuteIncreaseOrder<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the order has not expired, that its limit
+    /// is reached at the index vault's entry price for the order's direction, that an open
+    /// position has the order's direction and, when either vault requires position requests, that
+    /// the oracle prices were published after the order was created.
+    fn validate(&self, clock: &Clock) -> Result<()> {
+        let unix_timestamp: u64 = clock
+            .unix_timestamp
+            .try_into()
+            .or(Err(ErrorCode::InvalidTimestampConversion))?;
+        require!(
+            !self.order.is_expired(unix_timestamp),
+            ErrorCode::OrderExpired
+        );
+        require!(
+            self.order.is_triggered(
+                self.index_vault_cache()
+                    .get_entry_price(self.order.direction)
+            ),
+            ErrorCode::OrderNotTriggered
+        );
+        self.validate_increase_direction()?;
+        // like position requests, orders of vaults requiring them are only executed at a price
+        // published after they were created
+        if self.vault.has_position_requests || self.index_vault().has_position_requests {
+            require!(
+                self.vault_cache.oracle_price_slot > self.order.slot
+                    && self.index_vault_cache().oracle_price_slot > self.order.slot,
+                ErrorCode::PositionRequestPriceNotUpdated
+            );
+        }
+        Ok(())
+    }
+
+    /// Execute the order.
+    ///
+    /// The escrowed collateral opens or increases the position, see
+    /// [`EscrowedIncreaseContext::execute_increase`].
+    fn process(&mut self, clock: &Clock, escrow_bump: u8) -> Result<()> {
+        let size = self.order.size;
+        let (result, price) = self.execute_increase(clock, escrow_bump)?;
+
+        emit!(IncreaseOrderExecuted {
+            order: self.order.key(),
+            position: self.position.key(),
+            keeper: self.keeper.key(),
+            collateral_delta_usd: result.collateral_delta_usd,
+            size_delta: size,
+            fee: result.fee,
+            price,
+        });
+
+        Ok(())
+    }
+
+    /// Perform validation after performing an action.
+    ///
+    /// In this specific case we need to validate that we do not exceed the maximum allowed
+    /// leverage for this vault.
+    fn post_validation(&self) -> Result<()> {
+        self.validate_increase_leverage()
+    }
+}
+
+/// Anyone can execute an order once its limit is reached, which is paid for with its execution fee.
+pub fn handler(ctx: Context<ExecuteIncreaseOrder>) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
+    let escrow_bump = *ctx.bumps.get("escrow").unwrap();
+    ctx.accounts.validate(&clock)?;
+    ctx.accounts.process(&clock, escrow_bump)?;
+    ctx.accounts.post_validation()
+}
+
+impl_escrowed_increase_ctx! { ExecuteIncreaseOrder<'info>, order, order_token_account, order_authority }
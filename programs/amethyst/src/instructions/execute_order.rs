@@ -0,0 +1,245 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+use crate::{
+    args::DecreasePositionArgs,
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_position_escrow_ctx, PositionEscrowContext},
+    error::ErrorCode,
+    events::OrderExecuted,
+    state::{
+        order::Order,
+        position::Position,
+        vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::decrease_position,
+};
+
+#[derive(Accounts)]
+pub struct ExecuteOrder<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(
+        mut,
+        has_one = token_mint,
+        has_one = token_vault,
+        has_one = vault_signer,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's token account.
+    #[account(
+        mut,
+        token::authority = vault.vault_signer,
+        token::mint = token_mint
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The vault's token account authority.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer: AccountInfo<'info>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The order, its execution fee and rent go to the keeper.
+    #[account(
+        mut,
+        has_one = position,
+        close = keeper
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    /// The position to decrease.
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
+        constraint = position.authority == position_authority.key(),
+    )]
+    pub position: Box<Account<'info, Position>>,
+
+    /// The escrow of the position.
+    /// CHECK: The escrow PDA for the position.
+    #[account(
+        seeds = [
+            B_ESCROW,
+            position.key().as_ref(),
+            token_mint.key().as_ref()
+        ],
+        bump = position.escrow_bump_seed[0]
+    )]
+    pub escrow: AccountInfo<'info>,
+
+    /// The position's token account.
+    #[account(
+        mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
+        token::mint = token_mint,
+        token::authority = escrow,
+    )]
+    pub position_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The position's collateral asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: This is checked.
+    #[account(mut)]
+    pub position_authority: AccountInfo<'info>,
+
+    /// The position authority's token account, which receives the payout.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = position_authority,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The keeper.
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ExecuteOrder<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we want to validate that the order was created for the position
+    /// rather than for a previous one at the same address, which a closed position leaves behind,
+    /// and that the order's trigger is met at the index vault's exit price for the position.
+    fn validate(&self) -> Result<()> {
+        require!(
+            self.order.position_opened_slot == self.position.opened_slot,
+            ErrorCode::StaleOrder
+        );
+        require!(
+            self.order.is_triggered(
+                self.index_vault_cache()
+                    .get_exit_price(self.position.direction)
+            ),
+            ErrorCode::OrderNotTriggered
+        );
+        Ok(())
+    }
+
+    /// Execute the order.
+    ///
+    /// The position is decreased by the order's size, or closed if that exceeds its size,
+    /// and the payout goes to the position's authority.
+    fn process(&mut self, clock: &Clock) -> Result<()> {
+        // settle the accrued funding first, so that we act on the current collateral
+        self.settle_funding(clock)?;
+
+        let size_delta = self.order.size_delta.min(self.position.size);
+        let price = self
+            .index_vault_cache()
+            .get_exit_price(self.position.direction);
+        let collateral_price = self.vault_cache.oracle_price;
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+        let result = decrease_position(
+            &self.config,
+            &mut self.vault,
+            index_vault_cache,
+            &mut self.position,
+            &DecreasePositionArgs {
+                size_delta,
+                collateral_delta: None,
+            },
+            collateral_price,
+            self.position_vault.amount,
+        )?;
+        self.settle_decrease(&result, &self.position_token_account)?;
+
+        if self.position.size == 0 {
+            self.close_escrow(&self.position_authority)?;
+            self.position
+                .close(self.position_authority.to_account_info())?;
+        }
+
+        emit!(OrderExecuted {
+            order: self.order.key(),
+            position: self.position.key(),
+            keeper: self.keeper.key(),
+            size_delta,
+            has_profit: result.has_profit,
+            realised_pnl: result.realised_pnl,
+            fee: result.fee,
+            payout: result.payout,
+            price,
+        });
+
+        Ok(())
+    }
+}
+
+/// Anyone can execute an order once it is triggered, which is paid for with its execution fee.
+pub fn handler(ctx: Context<ExecuteOrder>) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
+    ctx.accounts.validate()?;
+    ctx.accounts.process(&clock)
+}
+
+impl_position_escrow_ctx! { ExecuteOrder<'info> }
@@ -2,16 +2,16 @@ use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::{
-    constants::B_ESCROW,
-    contexts::{impl_change_position_ctx, ChangePositionContext},
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_change_position_ctx, ChangePositionContext, PositionEscrowContext},
     error::ErrorCode,
     events::PositionIncreased,
     state::{
-        position::{Direction, Position},
+        position::Position,
         vault::{Vault, VaultCache},
         Config,
     },
-    utils::{get_next_average_price, token_to_usd, usd_to_token},
+    utils::increase_position,
 };
 
 #[derive(Accounts)]
@@ -19,6 +19,38 @@ pub struct IncreasePosition<'info> {
     /// The config.
     pub config: Box<Account<'info, Config>>,
 
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
     /// The vault's cache.
     #[account(
         mut,
@@ -47,10 +79,26 @@ pub struct IncreasePosition<'info> {
     /// CHECK: The vault signer is checked.
     pub vault_signer: AccountInfo<'info>,
 
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
     /// The user's position.
     #[account(
         mut,
         has_one = authority,
+        has_one = token_mint,
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
     )]
     pub position: Box<Account<'info, Position>>,
 
@@ -69,6 +117,11 @@ pub struct IncreasePosition<'info> {
     /// The position's token account.
     #[account(
         mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
         token::mint = token_mint,
         token::authority = escrow
     )]
@@ -82,7 +135,7 @@ pub struct IncreasePosition<'info> {
     )]
     pub user_token_account: Box<Account<'info, TokenAccount>>,
 
-    /// The position's asset.
+    /// The position's collateral asset.
     pub token_mint: Box<Account<'info, Mint>>,
 
     /// The position's authority.
@@ -98,102 +151,54 @@ pub struct IncreasePosition<'info> {
 impl<'info> IncreasePosition<'info> {
     /// Perform validation.
     ///
-    /// In this case we want to validate that the size delta is valid.
+    /// In this case we want to validate that neither vault requires position requests
+    /// and that the size delta is valid.
     fn validate(&self, size_delta: u64) -> Result<()> {
+        require!(
+            !self.vault.has_position_requests && !self.index_vault.has_position_requests,
+            ErrorCode::PositionRequestRequired
+        );
         require!(size_delta != 0, ErrorCode::InvalidSizeDelta);
         Ok(())
     }
 
-    /// Increase the position size
+    /// Increase the position size.
+    ///
+    /// The margin fee on the size delta is paid out of the collateral, the collateral tokens needed
+    /// to cover the size delta are reserved from the vault and the open interest is tracked by the
+    /// index vault, at whose entry price the position's average entry price is updated.
     fn process(&mut self, clock: &Clock, size_delta: u64) -> Result<()> {
-        let unix_timestamp: u64 = clock
-            .unix_timestamp
-            .try_into()
-            .or(Err(ErrorCode::InvalidTimestampConversion))?;
-        // update the funding rate of the vault before proceeding
-        self.vault.update_funding_rate(clock)?;
-
-        let decimals = self.vault.decimals;
-        // get the latest price from the vault cache
-        let price = 0;
-
-        // if the position size is zero, the avg entry price is the current price
-        if self.position.size == 0 {
-            self.position.avg_entry_price = 0;
-        }
-
-        // if the position size isn't zero and the size delta
-        if self.position.size != 0 && size_delta != 0 {
-            self.position.avg_entry_price = get_next_average_price(
-                self.position.size.into(),
-                self.position.avg_entry_price.into(),
-                size_delta.into(),
-                price, // todo
-            )?;
-        }
-
-        // colelct margin fees
-        let fee = self.vault.collect_margin_fees(
+        // settle the accrued funding first, so that we act on the current collateral
+        self.settle_funding(clock)?;
+
+        let collateral_price = self.vault_cache.oracle_price;
+        let price = self
+            .index_vault_cache()
+            .get_entry_price(self.position.direction);
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+        let result = increase_position(
             &self.config,
-            &self.vault_cache,
+            &mut self.vault,
+            self.token_vault.amount,
+            index_vault_cache,
+            &mut self.position,
+            0,
             size_delta,
-            self.position.size,
-            self.position.last_funding_index,
+            collateral_price,
         )?;
-        // transfer collateral and
-        let collateral_delta = 0;
-        let collateral_delta_usd = token_to_usd(collateral_delta, price, decimals)?;
-
-        self.position.collateral += collateral_delta_usd as u64;
-        require!(
-            self.position.collateral as u128 >= fee,
-            ErrorCode::InsufficientCollateralForFee
-        );
-        self.position.collateral = self.position.collateral.checked_sub(fee as u64).unwrap();
-        self.position.size += size_delta;
-        self.position.last_funding_index = self.vault_cache.funding_index;
-        self.position.last_funding_payment = unix_timestamp;
-
-        let reserve_delta = usd_to_token(size_delta.into(), price, decimals)?;
-        self.position.reserved_amount = reserve_delta as u64;
-
-        // TODO: add checks in [increase_reserved] for reserved <= pool_amount
-        self.vault.increase_reserved(reserve_delta);
-
-        match self.position.direction {
-            Direction::Long => {
-                // increase the amount of (position.size - position.collateral)
-                // if a fee is a charged on the collateral then this value needs to increase by the same amount
-                // since (position.size - position.collateral) will have increased that much
-                self.vault
-                    .increase_guaranteed_usd((size_delta as u128 + fee).into());
-                self.vault
-                    .decrease_guaranteed_usd(collateral_delta_usd.into())?;
-                // treat the collateral as part of the pool
-                self.vault.increase_pool_amount(collateral_delta.into());
-                // fees need to be taken from the pool since they are taken from collateral
-                // and collateral is part of the pool
-                self.vault
-                    .decrease_pool_amount(usd_to_token(fee, price, decimals)?)?;
-            }
-            Direction::Short => {
-                if self.vault_cache.short_open_interest == 0 {
-                    self.vault_cache.short_avg_entry_price = 0; // todo
-                } else {
-                    // calculate next short average entry price and increase short open interest
-                    let _ = self
-                        .vault_cache
-                        .get_next_short_average_entry_price(size_delta)?;
-                }
-            }
-        }
+        // collect the margin fee out of the collateral
+        self.release_collateral(result.fee_token_amount)?;
 
         emit!(PositionIncreased {
             position: self.position.key(),
             authority: self.authority.key(),
             size_delta: size_delta.into(),
-            collateral_delta_usd,
-            fee,
+            collateral_delta_usd: 0,
+            fee: result.fee.into(),
             price
         });
 
@@ -205,6 +210,10 @@ impl<'info> IncreasePosition<'info> {
     /// In this specific case we need to validate that, after potentially withdrawing collateral
     /// from the position, we do not exceed the maximum allowed leverage for this vault.
     fn post_validation(&self) -> Result<()> {
+        require!(
+            self.position.collateral > 0,
+            ErrorCode::PositionLeverageExceedsLimit
+        );
         let leverage = self.position.leverage();
         require!(
             leverage < self.vault.max_leverage.into(),
@@ -216,6 +225,7 @@ impl<'info> IncreasePosition<'info> {
 
 pub fn handler(ctx: Context<IncreasePosition>, size_delta: u64) -> Result<()> {
     let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
     ctx.accounts.validate(size_delta)?;
     ctx.accounts.process(&clock, size_delta)?;
     ctx.accounts.post_validation()?;
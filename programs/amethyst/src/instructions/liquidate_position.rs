@@ -1,18 +1,60 @@
-use std::sync::Arc;
-
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::{
-    constants::B_ESCROW,
+    args::DecreasePositionArgs,
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_position_escrow_ctx, PositionEscrowContext},
+    error::ErrorCode,
+    events::{PositionLiquidated, PositionPartiallyLiquidated},
     state::{
-        position::Position,
+        position::{Direction, Position},
         vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::{
+        decrease_position, get_funding_fee, get_liquidation_size_delta, get_liquidation_state,
+        get_position_fee, get_remaining_collateral, token_to_usd, usd_to_token, LiquidationState,
     },
 };
 
 #[derive(Accounts)]
 pub struct LiquidatePosition<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
     /// The vault's cache.
     #[account(
         mut,
@@ -41,10 +83,25 @@ pub struct LiquidatePosition<'info> {
     /// CHECK: The vault signer is checked.
     pub vault_signer: AccountInfo<'info>,
 
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
     #[account(
         mut,
+        has_one = token_mint,
         constraint = position.authority == position_authority.key(),
-        close = position_authority
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
     )]
     pub position: Box<Account<'info, Position>>,
 
@@ -60,40 +117,519 @@ pub struct LiquidatePosition<'info> {
     )]
     pub escrow: AccountInfo<'info>,
 
+    /// The position's token account.
     #[account(
         mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
         token::mint = token_mint,
-        close = position_authority,
+        token::authority = escrow,
     )]
     pub position_vault: Box<Account<'info, TokenAccount>>,
 
-    /// The position's asset.
+    /// The position's collateral asset.
     pub token_mint: Box<Account<'info, Mint>>,
 
     /// CHECK: This is checked.
+    #[account(mut)]
     pub position_authority: AccountInfo<'info>,
 
+    /// The position authority's token account, which receives the payout of partial liquidations.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = position_authority,
+    )]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The liquidator's token account, which receives the liquidation fee.
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = liquidator,
+    )]
+    pub liquidator_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The liquidator.
+    pub liquidator: Signer<'info>,
+
     pub token_program: Program<'info, Token>,
 }
 
 impl<'info> LiquidatePosition<'info> {
+    /// Gets the position's collateral left after its unrealized losses, accrued funding,
+    /// the fee to close it and the liquidation fee.
+    ///
+    /// Both the liquidation state and the size to close when partially liquidating the position
+    /// are derived from it.
+    fn get_remaining_collateral(&self) -> u64 {
+        let position = &self.position;
+        let fees = get_position_fee(&self.config, position.size)
+            + get_funding_fee(&self.vault, position.size, position.last_funding_index)
+            + self.config.liquidation_fee_usd;
+        get_remaining_collateral(
+            position.collateral,
+            position.size,
+            position.avg_entry_price,
+            self.index_vault_cache().get_exit_price(position.direction),
+            position.direction,
+            fees,
+        )
+    }
+
+    /// Gets the position's liquidation state.
+    ///
+    /// Positions that failed to pay funding are fully liquidated.
+    fn get_liquidation_state(&self, remaining_collateral: u64) -> LiquidationState {
+        if self.position.is_liquidatable {
+            return LiquidationState::Full;
+        }
+        get_liquidation_state(
+            self.position.size,
+            remaining_collateral,
+            self.config.maintenance_margin_bps,
+            self.vault.max_leverage,
+        )
+    }
+
     /// Perform validation.
     ///
-    /// In this specific case we want to validate that the position is eligible for liquidation.
-    fn validate(&self) -> Result<()> {
+    /// In this specific case we want to validate that the position is eligible for liquidation,
+    /// which is the case when its remaining collateral falls below the maintenance margin
+    /// or its leverage exceeds the maximum leverage allowed by the vault.
+    fn validate(&self, state: LiquidationState) -> Result<()> {
+        require!(
+            state != LiquidationState::None,
+            ErrorCode::PositionNotLiquidatable
+        );
         Ok(())
     }
 
+    /// Pays the liquidation fee to the liquidator out of the position's collateral, up to the
+    /// tokens held by its escrow.
+    ///
+    /// The fee is paid out of the collateral into the vault like the other fees, after which it is
+    /// paid out of the vault to the liquidator. Returns the amount of tokens paid.
+    fn pay_liquidation_fee(&mut self) -> Result<u64> {
+        let price = self.vault_cache.oracle_price;
+        let decimals = self.vault.decimals;
+        let fee_usd = self
+            .config
+            .liquidation_fee_usd
+            .min(self.position.collateral);
+        let fee_token_amount = usd_to_token(fee_usd.into(), price, decimals)?;
+        let liquidation_fee = fee_token_amount.min(self.position_vault.amount.into());
+        // only the value of the tokens actually paid is taken off of the collateral
+        let liquidation_fee_usd = if liquidation_fee < fee_token_amount {
+            token_to_usd(liquidation_fee, price, decimals)?.min(fee_usd.into()) as u64
+        } else {
+            fee_usd
+        };
+        let liquidation_fee = liquidation_fee as u64;
+
+        self.pay_from_collateral(liquidation_fee_usd, liquidation_fee)?;
+        self.pay_out(&self.liquidator_token_account, liquidation_fee)?;
+        self.vault.decrease_pool_amount(liquidation_fee.into())?;
+
+        Ok(liquidation_fee)
+    }
+
     /// Perform the liquidation.
-    fn process(&self) -> Result<()> {
+    ///
+    /// The tokens reserved for the position are released and the liquidation fee is paid
+    /// to the liquidator out of the collateral, the rest of which is moved into the vault,
+    /// which means that it accrues to LPs.
+    fn liquidate(&mut self, remaining_collateral: u64) -> Result<()> {
+        let liquidation_fee = self.pay_liquidation_fee()?;
+        let released_amount = self.position_vault.amount;
+        let price = self
+            .index_vault_cache()
+            .get_exit_price(self.position.direction);
+        let position = &self.position;
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+
+        self.vault
+            .decrease_reserved(position.reserved_amount.into())?;
+
+        match position.direction {
+            Direction::Long => {
+                index_vault_cache.decrease_long_open_interest(position.size);
+                // the guaranteed USD might have been accrued with different fees
+                let guaranteed_usd_delta = (position.size.saturating_sub(position.collateral)
+                    as u128)
+                    .min(self.vault.guaranteed_usd);
+                self.vault.decrease_guaranteed_usd(guaranteed_usd_delta)?;
+            }
+            Direction::Short => {
+                index_vault_cache.decrease_short_open_interest(position.size);
+                // unlike for longs, the collateral of short positions is not part of the pool yet
+                self.vault.increase_pool_amount(released_amount.into());
+            }
+        }
+
+        self.release_collateral(released_amount)?;
+        self.close_escrow(&self.position_authority)?;
+        self.position
+            .close(self.position_authority.to_account_info())?;
+
+        emit!(PositionLiquidated {
+            position: position.key(),
+            authority: position.authority,
+            size: position.size,
+            collateral: position.collateral,
+            remaining_collateral,
+            liquidator: self.liquidator.key(),
+            liquidation_fee,
+            price,
+        });
+
+        Ok(())
+    }
+
+    /// Perform a partial liquidation.
+    ///
+    /// The liquidation fee is paid to the liquidator out of the collateral and only enough of
+    /// the position is decreased for it to be brought back to the target leverage. The position is
+    /// fully liquidated instead when there is nothing to decrease or the whole of it would be.
+    fn partially_liquidate(&mut self, remaining_collateral: u64) -> Result<()> {
+        let size_delta = get_liquidation_size_delta(
+            self.position.size,
+            remaining_collateral,
+            self.config.liquidation_target_leverage,
+        );
+        if size_delta == 0 || size_delta == self.position.size {
+            return self.liquidate(remaining_collateral);
+        }
+
+        let liquidation_fee = self.pay_liquidation_fee()?;
+
+        let collateral_price = self.vault_cache.oracle_price;
+        let index_vault_cache = if self.index_vault.key() == self.vault.key() {
+            &mut self.vault_cache
+        } else {
+            &mut self.index_vault_cache
+        };
+        let result = decrease_position(
+            &self.config,
+            &mut self.vault,
+            index_vault_cache,
+            &mut self.position,
+            &DecreasePositionArgs {
+                size_delta,
+                collateral_delta: None,
+            },
+            collateral_price,
+            self.position_vault.amount,
+        )?;
+        self.settle_decrease(&result, &self.position_token_account)?;
+
+        emit!(PositionPartiallyLiquidated {
+            position: self.position.key(),
+            authority: self.position.authority,
+            size_delta,
+            size: self.position.size,
+            collateral: self.position.collateral,
+            liquidator: self.liquidator.key(),
+            liquidation_fee,
+            price: self
+                .index_vault_cache()
+                .get_exit_price(self.position.direction),
+        });
 
         Ok(())
     }
 }
 
-/// When we liquidate a position, we transfer the collateral into the vault.
+/// When we liquidate a position, we pay the liquidator and either transfer the remaining collateral
+/// into the vault, or decrease the position back to a safe leverage when partially liquidating it.
 pub fn handler(ctx: Context<LiquidatePosition>) -> Result<()> {
-    ctx.accounts.validate()?;
-    ctx.accounts.process()?;
-    Ok(())
+    let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
+    // settle the accrued funding first, positions that cannot afford it are flagged as liquidatable
+    ctx.accounts.try_settle_funding(&clock)?;
+
+    let remaining_collateral = ctx.accounts.get_remaining_collateral();
+    let state = ctx.accounts.get_liquidation_state(remaining_collateral);
+    ctx.accounts.validate(state)?;
+
+    match state {
+        LiquidationState::Partial => ctx.accounts.partially_liquidate(remaining_collateral),
+        _ => ctx.accounts.liquidate(remaining_collateral),
+    }
+}
+
+impl_position_escrow_ctx! { LiquidatePosition<'info> }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token;
+
+    use super::*;
+    use crate::constants::FUNDING_RATE_PRECISION;
+
+    fn account_info(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> AccountInfo<'static> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            true,
+            Box::leak(Box::new(0)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            false,
+            0,
+        )
+    }
+
+    fn program_account<T: AccountSerialize>(key: Pubkey, account: &T) -> AccountInfo<'static> {
+        let mut data = vec![];
+        account.try_serialize(&mut data).unwrap();
+        account_info(key, crate::ID, data)
+    }
+
+    fn token_account(
+        key: Pubkey,
+        mint: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+    ) -> AccountInfo<'static> {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let account = spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        account_info(key, spl_token::ID, data)
+    }
+
+    fn escrow_token_account(position: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[B_ESCROW_TOKEN_ACCOUNT, position.as_ref()], &crate::ID).0
+    }
+
+    /// Gets the accounts to liquidate a position, whose escrow token account is derived from the
+    /// position's address with the given function, after setting up the config, the vault and the
+    /// position, which returns the amount of tokens held by the escrow.
+    fn get_accounts(
+        position_vault: impl Fn(Pubkey) -> Pubkey,
+        setup: impl FnOnce(&mut Config, &mut Vault, &mut VaultCache, &mut Position) -> u64,
+    ) -> Vec<AccountInfo<'static>> {
+        let token_mint = Pubkey::new_unique();
+        let vault_address = Pubkey::new_unique();
+        let vault_cache_address = Pubkey::new_unique();
+        let position_address = Pubkey::new_unique();
+        let position_authority = Pubkey::new_unique();
+        let liquidator = Pubkey::new_unique();
+
+        let mut vault = Vault::default();
+        vault.token_mint = token_mint;
+        vault.token_vault = Pubkey::new_unique();
+        vault.vault_signer = Pubkey::new_unique();
+        let mut vault_cache = VaultCache::default();
+        vault_cache.vault = vault_address;
+
+        let (escrow, escrow_bump) = Pubkey::find_program_address(
+            &[B_ESCROW, position_address.as_ref(), token_mint.as_ref()],
+            &crate::ID,
+        );
+        let mut position = Position::default();
+        position.init(
+            position_address,
+            position_authority,
+            token_mint,
+            token_mint,
+            escrow_bump,
+            Direction::Long,
+            0,
+        );
+        let mut config = Config::default();
+        let escrow_amount = setup(&mut config, &mut vault, &mut vault_cache, &mut position);
+
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        let mint = spl_token::state::Mint {
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token::state::Mint::pack(mint, &mut mint_data).unwrap();
+
+        let mut liquidator_info = account_info(liquidator, Pubkey::default(), vec![]);
+        liquidator_info.is_signer = true;
+        let mut token_program = account_info(spl_token::ID, Pubkey::default(), vec![]);
+        token_program.executable = true;
+
+        vec![
+            program_account(Pubkey::new_unique(), &config),
+            program_account(vault_cache_address, &vault_cache),
+            program_account(vault_address, &vault),
+            account_info(Pubkey::default(), Pubkey::default(), vec![]),
+            account_info(Pubkey::default(), Pubkey::default(), vec![]),
+            program_account(vault_cache_address, &vault_cache),
+            program_account(vault_address, &vault),
+            token_account(vault.token_vault, token_mint, vault.vault_signer, 0),
+            account_info(vault.vault_signer, Pubkey::default(), vec![]),
+            account_info(Pubkey::default(), Pubkey::default(), vec![]),
+            account_info(Pubkey::default(), Pubkey::default(), vec![]),
+            program_account(position_address, &position),
+            account_info(escrow, Pubkey::default(), vec![]),
+            token_account(
+                position_vault(position_address),
+                token_mint,
+                escrow,
+                escrow_amount,
+            ),
+            account_info(token_mint, spl_token::ID, mint_data),
+            account_info(position_authority, Pubkey::default(), vec![]),
+            token_account(Pubkey::new_unique(), token_mint, position_authority, 0),
+            token_account(Pubkey::new_unique(), token_mint, liquidator, 0),
+            liquidator_info,
+            token_program,
+        ]
+    }
+
+    fn try_accounts(accounts: &[AccountInfo<'static>]) -> Result<LiquidatePosition<'static>> {
+        LiquidatePosition::try_accounts(
+            &crate::ID,
+            &mut &accounts[..],
+            &[],
+            &mut BTreeMap::new(),
+            &mut BTreeSet::new(),
+        )
+    }
+
+    #[test]
+    pub fn test_position_vault_must_be_the_escrow_token_account() -> Result<()> {
+        let accounts = get_accounts(escrow_token_account, |_, _, _, _| 0);
+        assert!(try_accounts(&accounts).is_ok());
+
+        // any other token account owned by the escrow is rejected
+        let accounts = get_accounts(|_| Pubkey::new_unique(), |_, _, _, _| 0);
+        assert_eq!(
+            try_accounts(&accounts).err(),
+            Some(anchor_lang::error::ErrorCode::ConstraintSeeds.into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_insufficient_collateral_for_funding() -> Result<()> {
+        // a position of 100 USD with 10 USD of collateral, owing 20 USD of funding
+        let accounts = get_accounts(escrow_token_account, |_, vault, vault_cache, position| {
+            vault.decimals = 6;
+            vault.cumulative_funding_rate = FUNDING_RATE_PRECISION as u128 / 5;
+            vault_cache.oracle_price = 10_000_000_000;
+            position.size = 100_000_000;
+            position.collateral = 10_000_000;
+            10_000_000
+        });
+        let clock = Clock::default();
+
+        // settling the funding fails
+        let mut liquidate_position = try_accounts(&accounts)?;
+        assert_eq!(
+            liquidate_position.settle_funding(&clock).err(),
+            Some(ErrorCode::InsufficientCollateralForFunding.into())
+        );
+
+        // while trying to settle it flags the position, which is then fully liquidated
+        let mut liquidate_position = try_accounts(&accounts)?;
+        assert!(!liquidate_position.try_settle_funding(&clock)?);
+        assert!(liquidate_position.position.is_liquidatable);
+        assert_eq!(liquidate_position.position.collateral, 10_000_000);
+        assert_eq!(liquidate_position.position.last_funding_index, 0);
+        let remaining_collateral = liquidate_position.get_remaining_collateral();
+        assert_eq!(
+            liquidate_position.get_liquidation_state(remaining_collateral),
+            LiquidationState::Full
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_partial_liquidation_guaranteed_usd() -> Result<()> {
+        // a long position of 1_000 USD with 90 USD of collateral, priced at 1 USD, which exceeds
+        // the maximum leverage of 10x once the fees are deducted
+        let accounts = get_accounts(
+            escrow_token_account,
+            |config, vault, vault_cache, position| {
+                config.margin_fee_bps = 10;
+                config.maintenance_margin_bps = 100;
+                config.liquidation_fee_usd = 5_000_000;
+                config.liquidation_target_leverage = 50_000;
+                vault.decimals = 6;
+                vault.max_leverage = 100_000;
+                vault.deposits = 10_000_000_000;
+                vault.reserved = 1_000_000_000;
+                vault.guaranteed_usd = 910_000_000;
+                vault_cache.oracle_price = 10_000_000_000;
+                vault_cache.min_price = 10_000_000_000;
+                vault_cache.max_price = 10_000_000_000;
+                vault_cache.long_open_interest = 1_000_000_000;
+                position.size = 1_000_000_000;
+                position.collateral = 90_000_000;
+                position.avg_entry_price = 10_000_000_000;
+                position.reserved_amount = 1_000_000_000;
+                90_000_000
+            },
+        );
+        let mut liquidate_position = try_accounts(&accounts)?;
+
+        // 84 USD remain after the fee to close the position and the liquidation fee
+        let remaining_collateral = liquidate_position.get_remaining_collateral();
+        assert_eq!(remaining_collateral, 84_000_000);
+        assert_eq!(
+            liquidate_position.get_liquidation_state(remaining_collateral),
+            LiquidationState::Partial
+        );
+        liquidate_position.partially_liquidate(remaining_collateral)?;
+
+        // 580 USD are closed to bring the position back to 5x, whose fee of 0.58 USD is paid
+        // along with the liquidation fee out of the collateral
+        let position = &liquidate_position.position;
+        assert_eq!(position.size, 420_000_000);
+        assert_eq!(position.collateral, 84_420_000);
+        // the guaranteed USD still tracks the size of the position net of its collateral
+        assert_eq!(liquidate_position.vault.guaranteed_usd, 335_580_000);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_capped_liquidation_fee() -> Result<()> {
+        // a long position whose escrow holds less than the liquidation fee of 5 USD
+        let accounts = get_accounts(
+            escrow_token_account,
+            |config, vault, vault_cache, position| {
+                config.liquidation_fee_usd = 5_000_000;
+                vault.decimals = 6;
+                vault.deposits = 10_000_000_000;
+                vault.guaranteed_usd = 910_000_000;
+                vault_cache.oracle_price = 10_000_000_000;
+                position.size = 1_000_000_000;
+                position.collateral = 90_000_000;
+                2_000_000
+            },
+        );
+        let mut liquidate_position = try_accounts(&accounts)?;
+
+        // only the value of the tokens paid is taken off of the collateral
+        assert_eq!(liquidate_position.pay_liquidation_fee()?, 2_000_000);
+        assert_eq!(liquidate_position.position.collateral, 88_000_000);
+        assert_eq!(liquidate_position.vault.guaranteed_usd, 912_000_000);
+        assert_eq!(liquidate_position.vault.deposits, 9_998_000_000);
+
+        Ok(())
+    }
 }
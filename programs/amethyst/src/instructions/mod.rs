@@ -1,31 +1,63 @@
+pub mod cancel_increase_order;
+pub mod cancel_order;
+pub mod cancel_position_request;
 pub mod close_global_cache;
 pub mod close_position;
 pub mod close_vault;
+pub mod crank_vault;
+pub mod create_config;
 pub mod create_global_cache;
+pub mod create_increase_order;
+pub mod create_order;
+pub mod create_position_request;
 pub mod create_vault;
 pub mod decrease_position;
 pub mod deposit_liquidity;
 pub mod deposit_collateral;
 pub mod enter_position;
+pub mod execute_increase_order;
+pub mod execute_order;
+pub mod execute_position_request;
 pub mod increase_position;
 pub mod liquidate_position;
 pub mod pay_funding;
 pub mod swap;
+pub mod swap_route;
+pub mod update_config;
+pub mod update_funding_params;
+pub mod update_token_weight;
+pub mod update_vault_params;
 pub mod withdraw_collateral;
 pub mod withdraw_liquidity;
 
+pub use cancel_increase_order::*;
+pub use cancel_order::*;
+pub use cancel_position_request::*;
 pub use close_global_cache::*;
 pub use close_position::*;
 pub use close_vault::*;
+pub use crank_vault::*;
+pub use create_config::*;
 pub use create_global_cache::*;
+pub use create_increase_order::*;
+pub use create_order::*;
+pub use create_position_request::*;
 pub use create_vault::*;
 pub use decrease_position::*;
 pub use deposit_liquidity::*;
 pub use deposit_collateral::*;
 pub use enter_position::*;
+pub use execute_increase_order::*;
+pub use execute_order::*;
+pub use execute_position_request::*;
 pub use increase_position::*;
 pub use liquidate_position::*;
 pub use pay_funding::*;
 pub use swap::*;
+pub use swap_route::*;
+pub use update_config::*;
+pub use update_funding_params::*;
+pub use update_token_weight::*;
+pub use update_vault_params::*;
 pub use withdraw_collateral::*;
 pub use withdraw_liquidity::*;
\ No newline at end of file
@@ -1,15 +1,50 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token::{Mint, Token, TokenAccount};
 
 use crate::{
-    constants::{B_ESCROW, SECONDS_IN_HOUR},
+    constants::{B_ESCROW, B_ESCROW_TOKEN_ACCOUNT},
+    contexts::{impl_position_escrow_ctx, PositionEscrowContext},
     error::ErrorCode,
-    state::{position::Position, vault::Vault, VaultCache},
-    utils::with_signer_pda,
+    state::{position::Position, vault::Vault, Config, VaultCache},
 };
 
 #[derive(Accounts)]
 pub struct PayFunding<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The index vault's cache, which tracks the open interest in the position's index token.
+    ///
+    /// It is declared before the vault's cache, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault_cache.vault == index_vault.key() @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of the position's index token.
+    ///
+    /// It is declared before the vault, which is written last when both are the same.
+    #[account(
+        mut,
+        constraint = index_vault.authority == vault.authority @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub index_vault: Box<Account<'info, Vault>>,
+
+    /// The index vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_oracle: AccountInfo<'info>,
+
+    /// The index vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the index vault's cache.
+    #[account(
+        address = index_vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub index_secondary_oracle: AccountInfo<'info>,
+
     /// The vault's cache.
     #[account(
         mut,
@@ -22,6 +57,7 @@ pub struct PayFunding<'info> {
         mut,
         has_one = token_mint,
         has_one = token_vault,
+        has_one = vault_signer,
     )]
     pub vault: Box<Account<'info, Vault>>,
 
@@ -33,7 +69,29 @@ pub struct PayFunding<'info> {
     )]
     pub token_vault: Box<Account<'info, TokenAccount>>,
 
-    #[account(mut)]
+    /// The vault's token account authority.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer: AccountInfo<'info>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = token_mint,
+        constraint = position.index_token_mint == index_vault.token_mint @ ErrorCode::InvalidTokenMint,
+    )]
     pub position: Box<Account<'info, Position>>,
 
     /// CHECK: The escrow PDA for the position.
@@ -49,6 +107,11 @@ pub struct PayFunding<'info> {
 
     #[account(
         mut,
+        seeds = [
+            B_ESCROW_TOKEN_ACCOUNT,
+            position.key().as_ref()
+        ],
+        bump,
         token::mint = token_mint,
         token::authority = escrow
     )]
@@ -62,52 +125,30 @@ pub struct PayFunding<'info> {
 impl<'info> PayFunding<'info> {
     /// Perform validation.
     ///
-    /// In this specific case we want to validate that enough time has passed for this position
-    /// to pay funding.
+    /// In this specific case we want to validate that the minimum interval between two funding
+    /// payments has passed since the position last paid funding.
     fn validate(&self, unix_timestamp: u64) -> Result<()> {
         require!(
-            self.position.last_funding_payment + SECONDS_IN_HOUR < unix_timestamp,
+            self.position.last_funding_payment + self.config.min_funding_interval as u64
+                <= unix_timestamp,
             ErrorCode::InvalidFundingInterval
         );
         Ok(())
     }
-
-    /// Transfer the funding amount to the vaults, which are the counter party.
-    fn transfer_funding(&self, amount: u64) -> Result<()> {
-        let cpi_signer = with_signer_pda(&self.escrow.to_account_info());
-        let cpi_seeds = &[&self.position.escrow_signer_seeds()[..]];
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = Transfer {
-            from: self.position_vault.to_account_info(),
-            to: self.token_vault.to_account_info(),
-            authority: cpi_signer,
-        };
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
-        transfer(cpi_ctx, amount)?;
-        Ok(())
-    }
-
-    /// Pay the funding rate.
-    fn pay_funding(&mut self, unix_timestamp: u64) -> Result<()> {
-        // TODO: get funding amount
-        let amount = 0u64;
-        self.transfer_funding(amount)?;
-        self.position
-            .pay_funding(self.vault_cache.funding_index, unix_timestamp);
-
-        Ok(())
-    }
 }
 
 /// Funding payments are taken off of the position's collateral.
 pub fn handler(ctx: Context<PayFunding>) -> Result<()> {
     let clock = Clock::get()?;
+    ctx.accounts.refresh_oracle_prices(&clock)?;
     let unix_timestamp: u64 = clock
         .unix_timestamp
         .try_into()
         .or(Err(ErrorCode::InvalidTimestampConversion))?;
 
     ctx.accounts.validate(unix_timestamp)?;
-    ctx.accounts.pay_funding(unix_timestamp)?;
+    ctx.accounts.settle_funding(&clock)?;
     Ok(())
 }
+
+impl_position_escrow_ctx! { PayFunding<'info> }
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{transfer, Mint, Token, TokenAccount, Transfer};
 
 use crate::{
     args::SwapArgs,
@@ -7,11 +7,16 @@ use crate::{
     state::{
         cache::Cache,
         vault::{Vault, VaultCache},
+        Config,
     },
+    utils::{refresh_oracle_price, swap_vaults, with_signer_pda},
 };
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
     /// The global cache.
     pub cache: Box<Account<'info, Cache>>,
 
@@ -19,7 +24,7 @@ pub struct Swap<'info> {
     #[account(
         mut,
         constraint = vault_a.token_vault == token_account_a.key(),
-        constraint = vault_a.token_mint == token_account_a.key()
+        constraint = vault_a.token_mint == token_mint_a.key()
     )]
     pub vault_a: Box<Account<'info, Vault>>,
 
@@ -30,6 +35,20 @@ pub struct Swap<'info> {
     )]
     pub vault_a_cache: Box<Account<'info, VaultCache>>,
 
+    /// The oracle account for asset A.
+    /// CHECK: The oracle account is checked against the one bound to the vault cache for asset A.
+    #[account(
+        address = vault_a_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle_a: AccountInfo<'info>,
+
+    /// The secondary oracle account for asset A, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault cache for asset A.
+    #[account(
+        address = vault_a_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle_a: AccountInfo<'info>,
+
     /// The vault's token account for asset A.
     #[account(
         mut,
@@ -53,10 +72,15 @@ pub struct Swap<'info> {
     #[account(
         mut,
         constraint = vault_b.token_vault == token_account_b.key(),
-        constraint = vault_b.token_mint == token_mint_b.key()
+        constraint = vault_b.token_mint == token_mint_b.key(),
+        constraint = vault_b.vault_signer == vault_signer_b.key()
     )]
     pub vault_b: Box<Account<'info, Vault>>,
 
+    /// The vault's token account authority for asset B.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer_b: AccountInfo<'info>,
+
     /// The vault cache for asset B.
     #[account(
         mut,
@@ -64,6 +88,20 @@ pub struct Swap<'info> {
     )]
     pub vault_b_cache: Box<Account<'info, VaultCache>>,
 
+    /// The oracle account for asset B.
+    /// CHECK: The oracle account is checked against the one bound to the vault cache for asset B.
+    #[account(
+        address = vault_b_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle_b: AccountInfo<'info>,
+
+    /// The secondary oracle account for asset B, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault cache for asset B.
+    #[account(
+        address = vault_b_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle_b: AccountInfo<'info>,
+
     /// The vault's token account for asset B.
     #[account(
         mut,
@@ -92,26 +130,79 @@ pub struct Swap<'info> {
 impl<'info> Swap<'info> {
     /// Perform validation.
     ///
-    /// In this specific case we need to check if there is enough liquidity available
-    /// to perform this swap.
-    fn validate(&self, min_amount_out: u64) -> Result<()> {
-        let available_liquidity = self
-            .vault_b
-            .deposits
-            .checked_sub(self.vault_b.reserved)
-            .unwrap();
+    /// In this specific case we need to check that the swap is between two different vaults
+    /// and that the amount being swapped is valid.
+    fn validate(&self, amount_in: u64) -> Result<()> {
         require!(
-            available_liquidity > min_amount_out.into(),
-            ErrorCode::InsufficientLiquidityForSwap
+            self.vault_a.key() != self.vault_b.key(),
+            ErrorCode::InvalidSwapVaults
         );
+        require!(amount_in > 0, ErrorCode::InvalidTokenAmount);
         Ok(())
     }
 
+    /// Transfer asset A from the user to the vault.
+    fn transfer_in(&self, amount: u64) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.user_token_account_a.to_account_info(),
+            to: self.token_account_a.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Transfer asset B from the vault to the user.
+    fn transfer_out(&self, amount: u64) -> Result<()> {
+        let cpi_signer = with_signer_pda(&self.vault_signer_b);
+        let cpi_seeds = &[&self.vault_b.vault_signer_seeds()[..]];
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.token_account_b.to_account_info(),
+            to: self.user_token_account_b.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        transfer(cpi_ctx, amount)
+    }
+
     /// Swap the assets.
-    /// 
+    ///
     /// In order to do this we have to calculate the swap fee and the tax amount
-    /// according to whether this swap improves or not the balances of each asset.
-    fn process(&self) -> Result<()> {
+    /// according to whether this swap improves or not the balances of each asset,
+    /// which are valued at the prices of their bound oracles.
+    fn process(&mut self, clock: &Clock, args: &SwapArgs) -> Result<()> {
+        refresh_oracle_price(
+            &mut self.vault_a_cache,
+            &self.oracle_a,
+            &self.secondary_oracle_a,
+            clock,
+        )?;
+        refresh_oracle_price(
+            &mut self.vault_b_cache,
+            &self.oracle_b,
+            &self.secondary_oracle_b,
+            clock,
+        )?;
+
+        let swap_result = swap_vaults(
+            &self.config,
+            &self.cache,
+            &mut self.vault_a,
+            &self.vault_a_cache,
+            &mut self.vault_b,
+            &self.vault_b_cache,
+            self.token_account_b.amount,
+            args.amount_in,
+        )?;
+        require!(
+            swap_result.amount_out >= args.min_amount_out,
+            ErrorCode::SlippageToleranceExceeded
+        );
+
+        self.transfer_in(args.amount_in)?;
+        self.transfer_out(swap_result.amount_out)?;
 
         Ok(())
     }
@@ -119,6 +210,7 @@ impl<'info> Swap<'info> {
 
 /// Here we perform a swap from token A to token B, if there is enough liqudiity available.
 pub fn handler(ctx: Context<Swap>, args: SwapArgs) -> Result<()> {
-    ctx.accounts.validate(args.min_amount_out)?;
-    Ok(())
+    let clock = Clock::get()?;
+    ctx.accounts.validate(args.amount_in)?;
+    ctx.accounts.process(&clock, &args)
 }
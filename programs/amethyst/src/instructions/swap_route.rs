@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+
+use crate::{
+    args::SwapRouteArgs,
+    error::ErrorCode,
+    events::SwapHopExecuted,
+    state::{
+        cache::Cache,
+        vault::{Vault, VaultCache},
+        Config,
+    },
+    utils::{refresh_oracle_price, swap_vaults, with_signer_pda},
+};
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache.
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The user's token account of the input asset.
+    #[account(
+        mut,
+        token::authority = authority,
+    )]
+    pub user_token_account_in: Box<Account<'info, TokenAccount>>,
+
+    /// The user's token account of the output asset.
+    #[account(
+        mut,
+        token::authority = authority,
+    )]
+    pub user_token_account_out: Box<Account<'info, TokenAccount>>,
+
+    /// The token account authority of the output asset's vault.
+    /// CHECK: The vault signer is checked against the last vault of the route.
+    pub vault_signer_out: AccountInfo<'info>,
+
+    /// The user's wallet.
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+/// A vault along a swap route.
+struct RouteVault<'info> {
+    vault: Account<'info, Vault>,
+    vault_cache: Account<'info, VaultCache>,
+    token_vault: Account<'info, TokenAccount>,
+    oracle: AccountInfo<'info>,
+    secondary_oracle: AccountInfo<'info>,
+}
+
+impl<'info> SwapRoute<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we need to check that the route goes through at least two
+    /// different vaults registered in the pool, that each vault is given along with its cache and
+    /// token account, while their oracle accounts are checked when refreshing the prices,
+    /// and that the user's token accounts match the assets at both ends of the route.
+    fn validate(&self, route: &[RouteVault<'info>], amount_in: u64) -> Result<()> {
+        require!(amount_in > 0, ErrorCode::InvalidTokenAmount);
+        require!(route.len() >= 2, ErrorCode::InvalidSwapVaults);
+
+        for (idx, route_vault) in route.iter().enumerate() {
+            let vault_address = route_vault.vault.key();
+            require!(
+                self.cache
+                    .is_registered(vault_address, route_vault.vault.cache_index)
+                    && route_vault.vault_cache.vault == vault_address
+                    && route_vault.vault.token_vault == route_vault.token_vault.key(),
+                ErrorCode::InvalidVaultAccounts
+            );
+            // a vault visited twice would have its changes overwritten when persisting the route
+            require!(
+                route[..idx].iter().all(|v| v.vault.key() != vault_address),
+                ErrorCode::InvalidSwapVaults
+            );
+        }
+
+        let first = &route[0].vault;
+        let last = &route[route.len() - 1].vault;
+        require!(
+            self.user_token_account_in.mint == first.token_mint
+                && self.user_token_account_out.mint == last.token_mint,
+            ErrorCode::InvalidTokenMint
+        );
+        require!(
+            self.vault_signer_out.key() == last.vault_signer,
+            ErrorCode::InvalidSigner
+        );
+        Ok(())
+    }
+
+    /// Transfer the input asset from the user to the first vault of the route.
+    fn transfer_in(&self, token_vault: &Account<'info, TokenAccount>, amount: u64) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.user_token_account_in.to_account_info(),
+            to: token_vault.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Transfer the output asset from the last vault of the route to the user.
+    fn transfer_out(&self, route_vault: &RouteVault<'info>, amount: u64) -> Result<()> {
+        let cpi_signer = with_signer_pda(&self.vault_signer_out);
+        let cpi_seeds = &[&route_vault.vault.vault_signer_seeds()[..]];
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: route_vault.token_vault.to_account_info(),
+            to: self.user_token_account_out.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Swap along the route.
+    ///
+    /// Each hop is charged its own fee and tax, the output of a hop being the input of the next.
+    /// Since the intermediate assets never leave their vaults, only the input and output assets
+    /// are transferred. Every asset is valued at the price of its vault's bound oracle.
+    fn process(
+        &self,
+        clock: &Clock,
+        route: &mut [RouteVault<'info>],
+        args: &SwapRouteArgs,
+    ) -> Result<()> {
+        for route_vault in route.iter_mut() {
+            refresh_oracle_price(
+                &mut route_vault.vault_cache,
+                &route_vault.oracle,
+                &route_vault.secondary_oracle,
+                clock,
+            )?;
+        }
+
+        let mut amount_in = args.amount_in;
+
+        for idx in 1..route.len() {
+            let (head, tail) = route.split_at_mut(idx);
+            let route_vault_in = &mut head[idx - 1];
+            let route_vault_out = &mut tail[0];
+
+            let swap_result = swap_vaults(
+                &self.config,
+                &self.cache,
+                &mut route_vault_in.vault,
+                &route_vault_in.vault_cache,
+                &mut route_vault_out.vault,
+                &route_vault_out.vault_cache,
+                route_vault_out.token_vault.amount,
+                amount_in,
+            )?;
+
+            emit!(SwapHopExecuted {
+                vault_in: route_vault_in.vault.key(),
+                vault_out: route_vault_out.vault.key(),
+                amount_in,
+                amount_out: swap_result.amount_out,
+                fee_bps: swap_result.fee_bps,
+            });
+
+            amount_in = swap_result.amount_out;
+        }
+
+        require!(
+            amount_in >= args.min_amount_out,
+            ErrorCode::SlippageToleranceExceeded
+        );
+
+        self.transfer_in(&route[0].token_vault, args.amount_in)?;
+        self.transfer_out(&route[route.len() - 1], amount_in)?;
+
+        Ok(())
+    }
+}
+
+/// Loads the vaults of a swap route from
+/// `[vault, vault_cache, token_vault, oracle, secondary_oracle]` quintuples.
+fn load_route<'info>(accounts: &[AccountInfo<'info>]) -> Result<Vec<RouteVault<'info>>> {
+    require!(accounts.len() % 5 == 0, ErrorCode::InvalidVaultAccounts);
+    accounts
+        .chunks(5)
+        .map(|accounts| {
+            Ok(RouteVault {
+                vault: Account::try_from(&accounts[0])?,
+                vault_cache: Account::try_from(&accounts[1])?,
+                token_vault: Account::try_from(&accounts[2])?,
+                oracle: accounts[3].clone(),
+                secondary_oracle: accounts[4].clone(),
+            })
+        })
+        .collect()
+}
+
+/// Here we perform a swap through an ordered route of vaults, e.g BONK -> USDC -> SOL.
+///
+/// The remaining accounts must contain a `[vault, vault_cache, token_vault, oracle, secondary_oracle]`
+/// quintuple, with writable vault, cache and token account, for each vault along the route,
+/// starting at the input asset and ending at the output asset.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, SwapRoute<'info>>,
+    args: SwapRouteArgs,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let mut route = load_route(ctx.remaining_accounts)?;
+
+    ctx.accounts.validate(&route, args.amount_in)?;
+    ctx.accounts.process(&clock, &mut route, &args)?;
+
+    // the route's vaults are not part of the instruction's accounts, so we persist them here
+    // along with their caches, whose oracle prices were refreshed
+    for route_vault in route.iter() {
+        route_vault.vault.exit(ctx.program_id)?;
+        route_vault.vault_cache.exit(ctx.program_id)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::CreateConfigArgs,
+    state::{cache::Cache, Config},
+    utils::validate_liquidation_target_leverage,
+};
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    /// The config.
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache, whose vaults the liquidation target leverage is checked against.
+    #[account(has_one = authority)]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The config's authority.
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we need to validate that the liquidation target leverage is below the
+    /// maximum leverage of every vault registered in the cache, which are passed as the remaining
+    /// accounts.
+    fn validate(&self, remaining_accounts: &[AccountInfo], args: &CreateConfigArgs) -> Result<()> {
+        validate_liquidation_target_leverage(
+            &self.cache,
+            remaining_accounts,
+            args.liquidation_target_leverage,
+        )
+    }
+
+    /// Update the config with the same parameters it is created with.
+    fn process(&mut self, args: &CreateConfigArgs) -> Result<()> {
+        self.config.set_params(args)
+    }
+}
+
+pub fn handler(ctx: Context<UpdateConfig>, args: CreateConfigArgs) -> Result<()> {
+    ctx.accounts.validate(ctx.remaining_accounts, &args)?;
+    ctx.accounts.process(&args)
+}
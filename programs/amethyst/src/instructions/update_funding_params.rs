@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::UpdateFundingParamsArgs,
+    constants::BASIS_POINTS_DIVISOR,
+    error::ErrorCode,
+    state::vault::{Vault, VaultCache},
+    utils::update_funding,
+};
+
+#[derive(Accounts)]
+pub struct UpdateFundingParams<'info> {
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(
+        mut,
+        has_one = authority,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's authority.
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateFundingParams<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we need to validate that the kink is within the utilisation range
+    /// and that the funding index precision is set, which can only be changed while no position
+    /// keeps track of the vault's funding indices, since they would be rounded when rescaled.
+    fn validate(&self, args: &UpdateFundingParamsArgs) -> Result<()> {
+        if let Some(kink_utilisation_bps) = args.kink_utilisation_bps {
+            require!(
+                kink_utilisation_bps as u64 <= BASIS_POINTS_DIVISOR,
+                ErrorCode::InvalidFundingParams
+            );
+        }
+        if let Some(funding_index_precision) = args.funding_index_precision {
+            require!(funding_index_precision > 0, ErrorCode::InvalidFundingParams);
+            require!(
+                self.vault.reserved == 0
+                    && self.vault_cache.long_open_interest == 0
+                    && self.vault_cache.short_open_interest == 0,
+                ErrorCode::CannotChangeFundingIndexPrecisionWithOpenPositions
+            );
+        }
+        Ok(())
+    }
+
+    /// Update the given funding rate parameters, leaving the others unchanged.
+    ///
+    /// The funding accrued so far is settled under the current parameters before they are replaced.
+    fn process(&mut self, clock: &Clock, args: &UpdateFundingParamsArgs) -> Result<()> {
+        update_funding(&mut self.vault, &mut self.vault_cache, clock)?;
+        let vault = &mut self.vault;
+        let base_rate = args.base_rate.unwrap_or(vault.funding_base_rate);
+        let slope_below_kink = args
+            .slope_below_kink
+            .unwrap_or(vault.funding_slope_below_kink);
+        let kink_utilisation_bps = args
+            .kink_utilisation_bps
+            .unwrap_or(vault.funding_kink_utilisation_bps);
+        let slope_above_kink = args
+            .slope_above_kink
+            .unwrap_or(vault.funding_slope_above_kink);
+        let max_rate_per_hour = args
+            .max_rate_per_hour
+            .unwrap_or(vault.max_funding_rate_per_hour);
+        vault.set_funding_params(
+            base_rate,
+            slope_below_kink,
+            kink_utilisation_bps,
+            slope_above_kink,
+            max_rate_per_hour,
+        );
+        if let Some(funding_index_precision) = args.funding_index_precision {
+            vault.set_funding_index_precision(&mut self.vault_cache, funding_index_precision);
+        }
+        if args.has_skew_funding.is_some() || args.skew_funding_rate.is_some() {
+            let has_skew_funding = args.has_skew_funding.unwrap_or(vault.has_skew_funding);
+            let skew_funding_rate = args.skew_funding_rate.unwrap_or(vault.skew_funding_rate);
+            vault.set_skew_funding_params(has_skew_funding, skew_funding_rate);
+        }
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<UpdateFundingParams>, args: UpdateFundingParamsArgs) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.validate(&args)?;
+    ctx.accounts.process(&clock, &args)
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::UpdateTokenWeightArgs,
+    error::ErrorCode,
+    state::{cache::Cache, vault::Vault},
+};
+
+#[derive(Accounts)]
+pub struct UpdateTokenWeight<'info> {
+    /// The global cache, which holds the target weights of the vaults.
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The vault of an asset.
+    #[account(
+        has_one = authority,
+        constraint = cache.is_registered(vault.key(), vault.cache_index) @ ErrorCode::InvalidVaultAccounts
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The pool's authority.
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateTokenWeight<'info> {
+    /// Update the vault's target weight.
+    ///
+    /// The sum of the target weights is updated along with it, so that the target debt amount of
+    /// every vault, which the dynamic fees are charged against, stays consistent.
+    fn process(&mut self, args: &UpdateTokenWeightArgs) -> Result<()> {
+        self.cache
+            .set_token_weight(self.vault.cache_index as usize, args.weight)
+    }
+}
+
+pub fn handler(ctx: Context<UpdateTokenWeight>, args: UpdateTokenWeightArgs) -> Result<()> {
+    ctx.accounts.process(&args)
+}
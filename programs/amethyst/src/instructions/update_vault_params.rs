@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::UpdateVaultParamsArgs,
+    state::vault::{Vault, VaultCache},
+};
+
+#[derive(Accounts)]
+pub struct UpdateVaultParams<'info> {
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault of an asset.
+    #[account(
+        mut,
+        has_one = authority
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's authority.
+    pub authority: Signer<'info>,
+}
+
+impl<'info> UpdateVaultParams<'info> {
+    /// Update the given parameters of the vault, leaving the others unchanged.
+    ///
+    /// Lowering the maximum open interest below the current one does not affect open positions,
+    /// it only prevents them from being entered or increased on that side.
+    fn process(&mut self, args: &UpdateVaultParamsArgs) -> Result<()> {
+        if let Some(has_position_requests) = args.has_position_requests {
+            self.vault.set_has_position_requests(has_position_requests);
+        }
+        if args.max_long_open_interest.is_some() || args.max_short_open_interest.is_some() {
+            let max_long_open_interest = args
+                .max_long_open_interest
+                .unwrap_or(self.vault_cache.max_long_open_interest);
+            let max_short_open_interest = args
+                .max_short_open_interest
+                .unwrap_or(self.vault_cache.max_short_open_interest);
+            self.vault_cache
+                .set_max_open_interest(max_long_open_interest, max_short_open_interest);
+        }
+        if let Some(max_oracle_deviation_bps) = args.max_oracle_deviation_bps {
+            self.vault_cache
+                .set_max_oracle_deviation_bps(max_oracle_deviation_bps);
+        }
+        Ok(())
+    }
+}
+
+pub fn handler(ctx: Context<UpdateVaultParams>, args: UpdateVaultParamsArgs) -> Result<()> {
+    ctx.accounts.process(&args)
+}
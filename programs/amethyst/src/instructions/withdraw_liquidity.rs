@@ -1,10 +1,207 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token::{burn, transfer, Burn, Mint, Token, TokenAccount, Transfer};
+
+use crate::{
+    args::WithdrawLiquidityArgs,
+    error::ErrorCode,
+    state::{Cache, Config, Vault, VaultCache},
+    utils::{
+        get_amount_after_fee, get_aum, get_fee_bps, get_lp_redemption_amount, load_vaults,
+        refresh_oracle_price, usd_to_token, with_signer_pda,
+    },
+};
 
 #[derive(Accounts)]
 pub struct WithdrawLiquidity<'info> {
+    /// The config.
+    pub config: Box<Account<'info, Config>>,
+
+    /// The global cache.
+    #[account(
+        mut,
+        has_one = lp_token_mint,
+    )]
+    pub cache: Box<Account<'info, Cache>>,
+
+    /// The vault's cache.
+    #[account(
+        mut,
+        has_one = vault
+    )]
+    pub vault_cache: Box<Account<'info, VaultCache>>,
+
+    /// The vault's oracle account.
+    /// CHECK: The oracle account is checked against the one bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub oracle: AccountInfo<'info>,
+
+    /// The vault's secondary oracle account, the system program when it has none.
+    /// CHECK: Checked against the secondary oracle bound to the vault's cache.
+    #[account(
+        address = vault_cache.get_secondary_oracle_account() @ ErrorCode::OracleAccountMismatch
+    )]
+    pub secondary_oracle: AccountInfo<'info>,
+
+    /// The vault of the redeemed asset.
+    #[account(
+        mut,
+        has_one = token_mint,
+        has_one = token_vault,
+        has_one = vault_signer,
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's token account.
+    #[account(
+        mut,
+        token::authority = vault.vault_signer,
+        token::mint = token_mint
+    )]
+    pub token_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The vault's token account authority.
+    /// CHECK: The vault signer is checked.
+    pub vault_signer: AccountInfo<'info>,
+
+    /// The redeemed asset.
+    pub token_mint: Box<Account<'info, Mint>>,
+
+    /// The user's token account.
+    #[account(
+        mut,
+        token::authority = authority,
+        token::mint = token_mint
+    )]
+    pub token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The LP token mint.
+    #[account(mut)]
+    pub lp_token_mint: Box<Account<'info, Mint>>,
+
+    /// The user's LP token account.
+    #[account(
+        mut,
+        token::authority = authority,
+        token::mint = lp_token_mint
+    )]
+    pub lp_token_account: Box<Account<'info, TokenAccount>>,
+
+    /// The user's wallet.
     pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
 }
 
-pub fn handler(ctx: Context<WithdrawLiquidity>) -> Result<()> {
-    Ok(())
-}
\ No newline at end of file
+impl<'info> WithdrawLiquidity<'info> {
+    /// Perform validation.
+    ///
+    /// In this specific case we need to validate that there are LP tokens to redeem and that the
+    /// vault is registered in the pool.
+    fn validate(&self, lp_amount: u64) -> Result<()> {
+        require!(
+            lp_amount > 0 && self.cache.lp_token_supply >= lp_amount,
+            ErrorCode::InvalidTokenAmount
+        );
+        require!(
+            self.cache
+                .is_registered(self.vault.key(), self.vault.cache_index),
+            ErrorCode::InvalidVaultAccounts
+        );
+        Ok(())
+    }
+
+    /// Burn the user's LP tokens.
+    fn burn_lp_tokens(&self, amount: u64) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Burn {
+            mint: self.lp_token_mint.to_account_info(),
+            from: self.lp_token_account.to_account_info(),
+            authority: self.authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        burn(cpi_ctx, amount)
+    }
+
+    /// Transfer the redeemed tokens from the vault to the user.
+    fn transfer_redemption(&self, amount: u64) -> Result<()> {
+        let cpi_signer = with_signer_pda(&self.vault_signer);
+        let cpi_seeds = &[&self.vault.vault_signer_seeds()[..]];
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: self.token_vault.to_account_info(),
+            to: self.token_account.to_account_info(),
+            authority: cpi_signer,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, cpi_seeds);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Withdraw the liquidity.
+    ///
+    /// The LP tokens are valued pro-rata to the pool's minimised AUM and redeemed for the vault's
+    /// asset at the upper bound of the vault's oracle price. The fee is kept in the vault, which
+    /// means that it accrues to LPs.
+    fn process(&mut self, aum: u128, args: &WithdrawLiquidityArgs) -> Result<()> {
+        let usd_amount = get_lp_redemption_amount(args.lp_amount, aum, self.cache.lp_token_supply);
+        let token_amount = usd_to_token(
+            usd_amount,
+            self.vault_cache.get_price(true),
+            self.vault.decimals,
+        )?;
+        let fee_bps = get_fee_bps(
+            &self.cache,
+            &self.vault,
+            usd_amount,
+            self.config.mint_burn_fee_bps,
+            self.config.tax_bps,
+            false,
+        );
+
+        let amount_out: u64 = get_amount_after_fee(token_amount, fee_bps)?
+            .try_into()
+            .or(Err(ErrorCode::InvalidTokenAmount))?;
+        require!(
+            amount_out >= args.min_amount_out,
+            ErrorCode::SlippageToleranceExceeded
+        );
+
+        require!(
+            self.vault.get_available_liquidity(self.token_vault.amount) >= amount_out.into(),
+            ErrorCode::CannotWithdrawReservedLiquidity
+        );
+
+        self.burn_lp_tokens(args.lp_amount)?;
+        self.transfer_redemption(amount_out)?;
+
+        self.vault.decrease_pool_amount(amount_out.into())?;
+        // the debt might have been accrued at a different price, so we never go below zero
+        let debt_delta = usd_amount.min(self.vault.debt_amount);
+        self.vault.decrease_debt_amount(debt_delta)?;
+        self.cache.decrease_lp_token_supply(args.lp_amount)?;
+
+        Ok(())
+    }
+}
+
+/// Withdraws liquidity from a vault by redeeming LP tokens.
+///
+/// The remaining accounts must contain every vault registered in the pool, along with their caches
+/// and oracle and secondary oracle accounts, which are used to compute the pool's AUM.
+pub fn handler(ctx: Context<WithdrawLiquidity>, args: WithdrawLiquidityArgs) -> Result<()> {
+    ctx.accounts.validate(args.lp_amount)?;
+
+    let clock = Clock::get()?;
+    refresh_oracle_price(
+        &mut ctx.accounts.vault_cache,
+        &ctx.accounts.oracle,
+        &ctx.accounts.secondary_oracle,
+        &clock,
+    )?;
+
+    let vaults = load_vaults(&ctx.accounts.cache, ctx.remaining_accounts, &clock)?;
+    let aum = get_aum(&vaults)?;
+
+    ctx.accounts.process(aum.min, &args)
+}
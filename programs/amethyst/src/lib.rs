@@ -19,6 +19,18 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub mod amethyst {
     use super::*;
 
+    pub fn cancel_increase_order(ctx: Context<CancelIncreaseOrder>) -> Result<()> {
+        instructions::cancel_increase_order::handler(ctx)
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        instructions::cancel_order::handler(ctx)
+    }
+
+    pub fn cancel_position_request(ctx: Context<CancelPositionRequest>) -> Result<()> {
+        instructions::cancel_position_request::handler(ctx)
+    }
+
     pub fn close_global_cache(ctx: Context<CloseGlobalCache>) -> Result<()> {
         instructions::close_global_cache::handler(ctx)
     }
@@ -31,22 +43,70 @@ pub mod amethyst {
         instructions::close_vault::handler(ctx)
     }
 
+    pub fn crank_vault(ctx: Context<CrankVault>) -> Result<()> {
+        instructions::crank_vault::handler(ctx)
+    }
+
+    pub fn create_config(ctx: Context<CreateConfig>, args: CreateConfigArgs) -> Result<()> {
+        instructions::create_config::handler(ctx, args)
+    }
+
     pub fn create_global_cache(ctx: Context<CreateGlobalCache>) -> Result<()> {
         instructions::create_global_cache::handler(ctx)
     }
 
-    pub fn create_vault(ctx: Context<CloseVault>) -> Result<()> {
-        instructions::close_vault::handler(ctx)
+    pub fn create_increase_order(
+        ctx: Context<CreateIncreaseOrder>,
+        args: CreateIncreaseOrderArgs,
+    ) -> Result<()> {
+        instructions::create_increase_order::handler(ctx, args)
+    }
+
+    pub fn create_order(ctx: Context<CreateOrder>, args: CreateOrderArgs) -> Result<()> {
+        instructions::create_order::handler(ctx, args)
+    }
+
+    pub fn create_position_request(
+        ctx: Context<CreatePositionRequest>,
+        args: CreatePositionRequestArgs,
+    ) -> Result<()> {
+        instructions::create_position_request::handler(ctx, args)
+    }
+
+    pub fn create_vault(ctx: Context<CreateVault>, args: CreateVaultArgs) -> Result<()> {
+        instructions::create_vault::handler(ctx, args)
+    }
+
+    pub fn decrease_position(
+        ctx: Context<DecreasePosition>,
+        args: DecreasePositionArgs,
+    ) -> Result<()> {
+        instructions::decrease_position::handler(ctx, args)
     }
 
-    pub fn decrease_position(ctx: Context<DecreasePosition>, size_delta: u64) -> Result<()> {
-        instructions::decrease_position::handler(ctx, size_delta)
+    pub fn deposit_liquidity(
+        ctx: Context<DepositLiquidity>,
+        args: DepositLiquidityArgs,
+    ) -> Result<()> {
+        instructions::deposit_liquidity::handler(ctx, args)
     }
 
     pub fn enter_position(ctx: Context<EnterPosition>, args: EnterPositionArgs) -> Result<()> {
         instructions::enter_position::handler(ctx, args)
     }
 
+    pub fn execute_increase_order(ctx: Context<ExecuteIncreaseOrder>) -> Result<()> {
+        instructions::execute_increase_order::handler(ctx)
+    }
+
+    pub fn execute_order(ctx: Context<ExecuteOrder>) -> Result<()> {
+        instructions::execute_order::handler(ctx)
+    }
+
+    pub fn execute_position_request(ctx: Context<ExecutePositionRequest>) -> Result<()> {
+        instructions::execute_position_request::handler(ctx)
+    }
+
     pub fn increase_position(ctx: Context<IncreasePosition>, size_delta: u64) -> Result<()> {
         instructions::increase_position::handler(ctx, size_delta)
     }
@@ -62,6 +122,45 @@ pub mod amethyst {
     pub fn swap(ctx: Context<Swap>, args: SwapArgs) -> Result<()> {
         instructions::swap::handler(ctx, args)
     }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapRoute<'info>>,
+        args: SwapRouteArgs,
+    ) -> Result<()> {
+        instructions::swap_route::handler(ctx, args)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, args: CreateConfigArgs) -> Result<()> {
+        instructions::update_config::handler(ctx, args)
+    }
+
+    pub fn update_funding_params(
+        ctx: Context<UpdateFundingParams>,
+        args: UpdateFundingParamsArgs,
+    ) -> Result<()> {
+        instructions::update_funding_params::handler(ctx, args)
+    }
+
+    pub fn update_token_weight(
+        ctx: Context<UpdateTokenWeight>,
+        args: UpdateTokenWeightArgs,
+    ) -> Result<()> {
+        instructions::update_token_weight::handler(ctx, args)
+    }
+
+    pub fn update_vault_params(
+        ctx: Context<UpdateVaultParams>,
+        args: UpdateVaultParamsArgs,
+    ) -> Result<()> {
+        instructions::update_vault_params::handler(ctx, args)
+    }
+
+    pub fn withdraw_liquidity(
+        ctx: Context<WithdrawLiquidity>,
+        args: WithdrawLiquidityArgs,
+    ) -> Result<()> {
+        instructions::withdraw_liquidity::handler(ctx, args)
+    }
 }
 
 #[derive(Accounts)]
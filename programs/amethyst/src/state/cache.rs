@@ -1,22 +1,121 @@
 use anchor_lang::prelude::*;
 use jet_proto_proc_macros::assert_size;
 
-#[assert_size(aligns, 40)]
+use crate::{constants::MAX_VAULTS, error::ErrorCode};
+
+#[assert_size(aligns, 640)]
 #[account]
+#[derive(Default)]
 #[repr(C)]
 pub struct Cache {
+    /// The cache signer seed bump.
+    pub cache_signer_seed_bump: [u8; 1], // 1
+    /// The number of vaults registered in the cache.
+    pub vault_count: u8, // 2
+    padding: [u8; 14], // 16
+
     /// The authority.
-    pub authority: Pubkey, // 32
+    pub authority: Pubkey, // 48
+    /// The address of the cache.
+    pub self_address: Pubkey, // 80
+    /// The cache signer PDA, which is the authority of the LP token mint.
+    pub cache_signer: Pubkey, // 112
+    /// The LP token mint.
+    pub lp_token_mint: Pubkey, // 144
+
+    /// The supply of LP tokens.
+    pub lp_token_supply: u64, // 152
+    /// The sum of the target weights of all vaults.
+    pub total_token_weights: u64, // 160
 
-    pub lp_token_supply: u64, // 40
+    /// The vaults registered in the pool, indexed by [`Vault::cache_index`].
+    pub vaults: [Pubkey; MAX_VAULTS], // 544
+    /// The target weight of each vault, indexed by [`Vault::cache_index`].
+    pub token_weights: [u64; MAX_VAULTS], // 640
 }
 
 impl Cache {
+    /// Initialize the cache.
+    pub fn init(
+        &mut self,
+        self_address: Pubkey,
+        authority: Pubkey,
+        cache_signer: Pubkey,
+        lp_token_mint: Pubkey,
+        cache_signer_bump: u8,
+    ) {
+        self.self_address = self_address;
+        self.authority = authority;
+        self.cache_signer = cache_signer;
+        self.lp_token_mint = lp_token_mint;
+        self.cache_signer_seed_bump = [cache_signer_bump];
+    }
+
+    /// Registers a vault in the pool.
+    ///
+    /// Returns the vault's index in the cache.
+    pub fn register_vault(&mut self, vault: Pubkey) -> Result<u8> {
+        let cache_index = self.vault_count;
+        require!(
+            (cache_index as usize) < MAX_VAULTS,
+            ErrorCode::MaxVaultsExceeded
+        );
+        self.vaults[cache_index as usize] = vault;
+        self.vault_count += 1;
+        Ok(cache_index)
+    }
+
+    /// Whether the vault is registered in the pool at the given index.
+    pub fn is_registered(&self, vault: Pubkey, cache_index: u8) -> bool {
+        self.get_vaults().get(cache_index as usize) == Some(&vault)
+    }
+
+    /// Gets the cache's signer seeds.
+    pub fn cache_signer_seeds(&self) -> [&[u8]; 3] {
+        use crate::constants::B_CACHE_SIGNER;
+        [
+            B_CACHE_SIGNER,
+            self.self_address.as_ref(),
+            &self.cache_signer_seed_bump,
+        ]
+    }
+
+    /// Gets the vaults registered in the pool.
+    pub fn get_vaults(&self) -> &[Pubkey] {
+        &self.vaults[..self.vault_count as usize]
+    }
+
+    /// Gets the target weight of the vault at the given index.
     pub fn get_weight(&self, index: usize) -> u64 {
-        0u64
+        self.token_weights[index]
+    }
+
+    /// Sets the target weight of the vault at the given index, keeping the sum of the target
+    /// weights of all vaults in sync.
+    pub fn set_token_weight(&mut self, index: usize, weight: u64) -> Result<()> {
+        self.total_token_weights = (self.total_token_weights - self.token_weights[index])
+            .checked_add(weight)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.token_weights[index] = weight;
+        Ok(())
     }
 
+    /// Gets the sum of the target weights of all vaults.
     pub fn get_total_token_weights(&self) -> u64 {
-        0u64
+        self.total_token_weights
+    }
+
+    /// Increases the LP token supply.
+    pub fn increase_lp_token_supply(&mut self, amount: u64) {
+        self.lp_token_supply += amount;
+    }
+
+    /// Decreases the LP token supply.
+    pub fn decrease_lp_token_supply(&mut self, amount: u64) -> Result<()> {
+        self.lp_token_supply = self
+            .lp_token_supply
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
     }
 }
@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
 use jet_proto_proc_macros::assert_size;
 
-use crate::args::CreateConfigArgs;
+use crate::{args::CreateConfigArgs, constants::BASIS_POINTS_DIVISOR, error::ErrorCode};
 
-#[assert_size(aligns, 64)]
+#[assert_size(aligns, 80)]
 #[account]
+#[derive(Default)]
 #[repr(C)]
 pub struct Config {
     /// The config version.
@@ -23,18 +24,58 @@ pub struct Config {
     pub stable_swap_fee_bps: u16, // 26
     /// The fee applied to margin position.
     pub margin_fee_bps: u16, // 28
-    padding2: [u16; 2], // 32
+    /// The minimum collateral a position must retain, relative to its size.
+    pub maintenance_margin_bps: u16, // 30
+    padding2: [u16; 1], // 32
 
     pub authority: Pubkey, // 64
+
+    /// The fee paid to the liquidator of a position, denominated in USD.
+    pub liquidation_fee_usd: u64, // 72
+    /// The leverage partially liquidated positions are brought back to, represented in basis points.
+    ///
+    /// It is below the maximum leverage of every vault.
+    pub liquidation_target_leverage: u32, // 76
+    /// The minimum number of seconds between two funding payments of a position made through
+    /// `pay_funding`, zero to allow them at any time.
+    pub min_funding_interval: u32, // 80
 }
 
 impl Config {
-    pub fn init(&mut self, authority: Pubkey, args: &CreateConfigArgs) {
+    pub fn init(&mut self, authority: Pubkey, args: &CreateConfigArgs) -> Result<()> {
         self.authority = authority;
+        self.set_params(args)
+    }
+
+    /// Sets the config's parameters.
+    ///
+    /// A fee along with the tax added on top of it can never exceed the amount it is charged on.
+    /// The liquidation target leverage is checked to be set here, while it is up to the caller to
+    /// check it against the maximum leverage of the vaults.
+    pub fn set_params(&mut self, args: &CreateConfigArgs) -> Result<()> {
+        let max_bps = BASIS_POINTS_DIVISOR as u32;
+        require!(
+            args.mint_burn_fee_bps as u32 + args.tax_bps as u32 <= max_bps
+                && args.swap_fee_bps as u32 + args.tax_bps as u32 <= max_bps
+                && args.stable_swap_fee_bps as u32 + args.stable_tax_bps as u32 <= max_bps
+                && args.margin_fee_bps as u32 <= max_bps
+                && args.maintenance_margin_bps as u32 <= max_bps,
+            ErrorCode::InvalidFee
+        );
+        require!(
+            args.liquidation_target_leverage > 0,
+            ErrorCode::InvalidLiquidationTargetLeverage
+        );
         self.tax_bps = args.tax_bps;
         self.stable_tax_bps = args.stable_tax_bps;
         self.mint_burn_fee_bps = args.mint_burn_fee_bps;
         self.swap_fee_bps = args.swap_fee_bps;
+        self.stable_swap_fee_bps = args.stable_swap_fee_bps;
         self.margin_fee_bps = args.margin_fee_bps;
+        self.maintenance_margin_bps = args.maintenance_margin_bps;
+        self.liquidation_fee_usd = args.liquidation_fee_usd;
+        self.liquidation_target_leverage = args.liquidation_target_leverage;
+        self.min_funding_interval = args.min_funding_interval;
+        Ok(())
     }
 }
@@ -1,11 +1,15 @@
 pub mod cache;
 pub mod config;
 pub mod oracle;
+pub mod order;
 pub mod position;
+pub mod position_request;
 pub mod vault;
 
 pub use cache::*;
 pub use config::*;
 pub use oracle::*;
+pub use order::*;
 pub use position::*;
+pub use position_request::*;
 pub use vault::*;
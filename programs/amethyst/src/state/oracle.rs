@@ -9,6 +9,16 @@ pub enum PriceFeedResult {
     Bounded(BoundedPrice),
 }
 
+impl PriceFeedResult {
+    /// Gets the price, regardless of its bounds.
+    pub fn price(&self) -> u128 {
+        match self {
+            PriceFeedResult::Confident(price) => *price,
+            PriceFeedResult::Bounded(bounded_price) => bounded_price.price,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct BoundedPrice {
     /// The price.
@@ -20,7 +30,7 @@ pub struct BoundedPrice {
 }
 
 #[assert_size(aligns, 40)]
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
 pub struct SwitchboardOracleInfo {
     /// The aggregator account.
     pub aggregator_account: Pubkey,
@@ -29,7 +39,7 @@ pub struct SwitchboardOracleInfo {
 }
 
 #[assert_size(aligns, 40)]
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
 pub struct PythOracleInfo {
     /// The pyth price account.
     pub price_account: Pubkey,
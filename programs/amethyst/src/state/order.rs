@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use jet_proto_proc_macros::assert_size;
+
+use super::position::Direction;
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerCondition {
+    /// The order triggers when the price is at or above the trigger price.
+    Above,
+    /// The order triggers when the price is at or below the trigger price.
+    Below,
+}
+
+impl Default for TriggerCondition {
+    fn default() -> Self {
+        Self::Above
+    }
+}
+
+/// A take-profit or stop-loss order, which decreases a [`Position`] once its trigger is met.
+///
+/// The execution fee is held in the order's lamports and goes to the keeper executing it.
+#[assert_size(aligns, 136)]
+#[account]
+#[repr(C)]
+pub struct Order {
+    /// The order bump seed.
+    pub bump_seed: [u8; 1], // 1
+    /// The condition under which the order triggers.
+    pub trigger_condition: TriggerCondition, // 2
+    padding: [u8; 6], // 8
+    /// The slot at which the position was opened, the order cannot decrease a position opened
+    /// later at the same address.
+    pub position_opened_slot: u64, // 16
+
+    /// The address of the order.
+    pub self_address: Pubkey, // 48
+    /// The order authority, which is the position's authority.
+    pub authority: Pubkey, // 80
+    /// The position to decrease.
+    pub position: Pubkey, // 112
+
+    /// The trigger price of the index token.
+    pub trigger_price: u64, // 120
+    /// The size to close, denominated in USD.
+    pub size_delta: u64, // 128
+    /// The fee paid to the keeper executing the order, in lamports.
+    pub execution_fee: u64, // 136
+}
+
+impl Order {
+    /// Initializes the order.
+    pub fn init(
+        &mut self,
+        self_address: Pubkey,
+        authority: Pubkey,
+        position: Pubkey,
+        position_opened_slot: u64,
+        bump_seed: u8,
+    ) {
+        self.self_address = self_address;
+        self.authority = authority;
+        self.position = position;
+        self.position_opened_slot = position_opened_slot;
+        self.bump_seed = [bump_seed];
+    }
+
+    /// Whether the order triggers at the given price.
+    pub fn is_triggered(&self, price: u64) -> bool {
+        match self.trigger_condition {
+            TriggerCondition::Above => price >= self.trigger_price,
+            TriggerCondition::Below => price <= self.trigger_price,
+        }
+    }
+}
+
+/// A limit order, which opens or increases a [`Position`] once the index price crosses its limit
+/// price: at or below it for longs and at or above it for shorts.
+///
+/// The collateral is escrowed in the order's token account, while the execution fee is held in the
+/// order's lamports and goes to the keeper executing it.
+#[assert_size(aligns, 184)]
+#[account]
+#[repr(C)]
+pub struct IncreaseOrder {
+    /// The order bump seed.
+    pub bump_seed: [u8; 1], // 1
+    /// The direction of the position.
+    pub direction: Direction, // 2
+    padding: [u8; 6], // 8
+    /// The slot at which the order was created.
+    pub slot: u64, // 16
+
+    /// The address of the order.
+    pub self_address: Pubkey, // 48
+    /// The order authority, which is the position's authority.
+    pub authority: Pubkey, // 80
+    /// The position's collateral asset.
+    pub token_mint: Pubkey, // 112
+    /// The position's index asset.
+    pub index_token_mint: Pubkey, // 144
+
+    /// The escrowed collateral, in native token amount.
+    pub collateral: u64, // 152
+    /// The size to open or increase the position by, denominated in USD.
+    pub size: u64, // 160
+    /// The limit price of the index token.
+    pub limit_price: u64, // 168
+    /// The timestamp after which the order can no longer be executed, or zero if it never expires.
+    pub expires_at: u64, // 176
+    /// The fee paid to the keeper executing the order, in lamports.
+    pub execution_fee: u64, // 184
+}
+
+impl IncreaseOrder {
+    /// Initializes the order.
+    pub fn init(
+        &mut self,
+        self_address: Pubkey,
+        authority: Pubkey,
+        token_mint: Pubkey,
+        index_token_mint: Pubkey,
+        bump_seed: u8,
+        direction: Direction,
+    ) {
+        self.self_address = self_address;
+        self.authority = authority;
+        self.token_mint = token_mint;
+        self.index_token_mint = index_token_mint;
+        self.bump_seed = [bump_seed];
+        self.direction = direction;
+    }
+
+    /// Gets the order signer seeds, the order being the authority of its token account.
+    pub fn signer_seeds(&self) -> [&[u8]; 5] {
+        use crate::constants::B_INCREASE_ORDER;
+        [
+            B_INCREASE_ORDER,
+            self.authority.as_ref(),
+            self.index_token_mint.as_ref(),
+            self.token_mint.as_ref(),
+            &self.bump_seed,
+        ]
+    }
+
+    /// Whether the order's limit is reached at the given price.
+    pub fn is_triggered(&self, price: u64) -> bool {
+        match self.direction {
+            Direction::Long => price <= self.limit_price,
+            Direction::Short => price >= self.limit_price,
+        }
+    }
+
+    /// Whether the order has expired at the given timestamp.
+    pub fn is_expired(&self, unix_timestamp: u64) -> bool {
+        self.expires_at != 0 && unix_timestamp >= self.expires_at
+    }
+}
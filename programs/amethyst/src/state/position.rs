@@ -3,7 +3,7 @@ use jet_proto_proc_macros::assert_size;
 
 use crate::constants::BASIS_POINTS_DIVISOR;
 
-#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     /// A long position where the collateral and underlying represent an asset.
     Long,
@@ -17,25 +17,31 @@ impl Default for Direction {
     }
 }
 
-#[assert_size(aligns, 160)]
+#[assert_size(aligns, 208)]
 #[account]
+#[derive(Default)]
 #[repr(C)]
 pub struct Position {
     /// The escrow bump seed.
     pub escrow_bump_seed: [u8; 1], // 1
     /// The direction of the position.
     pub direction: Direction, // 2
-    padding: [u8; 14], // 16
+    /// Whether the position failed to pay funding, which makes it eligible for liquidation.
+    pub is_liquidatable: bool, // 3
+    padding: [u8; 5], // 8
+    /// The slot at which the position was opened, which tells it apart from a previous position
+    /// at the same address.
+    pub opened_slot: u64, // 16
 
     pub self_address: Pubkey, // 48
     /// The position authority.
     pub authority: Pubkey, // 80
-    /// The token mint of the position.
-    pub token_mint: Pubkey, // 12
+    /// The token mint of the position's collateral, which its reserves are denominated in.
+    pub token_mint: Pubkey, // 112
 
-    /// The collateral of the position.
+    /// The collateral of the position, denominated in USD.
     pub collateral: u64, // 120
-    /// The position size.
+    /// The position size, denominated in USD.
     pub size: u64, // 128
     /// The average entry price.
     pub avg_entry_price: u64, // 136
@@ -45,6 +51,10 @@ pub struct Position {
     pub last_funding_payment: u64, // 152
     /// The amount of reserved tokens for this position.
     pub reserved_amount: u64, // 160
+    /// The last skew funding payment index.
+    pub last_skew_funding_index: i128, // 176
+    /// The token mint of the position's index, which its price exposure is to.
+    pub index_token_mint: Pubkey, // 208
 }
 
 impl Position {
@@ -71,22 +81,32 @@ impl Position {
     /// Initializes the position
     pub fn init(
         &mut self,
+        self_address: Pubkey,
         authority: Pubkey,
         token_mint: Pubkey,
-        collateral: u64,
-        size: u64,
-        avg_entry_price: u64,
+        index_token_mint: Pubkey,
+        escrow_bump_seed: u8,
+        direction: Direction,
+        opened_slot: u64,
     ) {
+        self.self_address = self_address;
         self.authority = authority;
         self.token_mint = token_mint;
-        self.collateral = collateral;
-        self.size = size;
-        self.avg_entry_price = avg_entry_price;
+        self.index_token_mint = index_token_mint;
+        self.escrow_bump_seed = [escrow_bump_seed];
+        self.direction = direction;
+        self.opened_slot = opened_slot;
     }
 
     /// Pays funding.
-    pub fn pay_funding(&mut self, funding_index: u64, current_timestamp: u64) {
+    pub fn pay_funding(
+        &mut self,
+        funding_index: u64,
+        skew_funding_index: i128,
+        current_timestamp: u64,
+    ) {
         self.last_funding_index = funding_index;
+        self.last_skew_funding_index = skew_funding_index;
         self.last_funding_payment = current_timestamp;
     }
 }
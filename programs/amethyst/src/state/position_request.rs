@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use jet_proto_proc_macros::assert_size;
+
+use crate::constants::POSITION_REQUEST_TIMEOUT_SLOTS;
+
+use super::position::Direction;
+
+/// A request to enter or increase a [`Position`], which a keeper executes with an oracle price
+/// published after the request was made, so that it cannot be made against a stale price.
+///
+/// The collateral is escrowed in the request's token account, while the execution fee is held in
+/// the request's lamports and goes to the keeper executing it.
+#[assert_size(aligns, 176)]
+#[account]
+#[repr(C)]
+pub struct PositionRequest {
+    /// The request bump seed.
+    pub bump_seed: [u8; 1], // 1
+    /// The direction of the position.
+    pub direction: Direction, // 2
+    padding: [u8; 14], // 16
+
+    /// The address of the request.
+    pub self_address: Pubkey, // 48
+    /// The request authority, which is the position's authority.
+    pub authority: Pubkey, // 80
+    /// The position's collateral asset.
+    pub token_mint: Pubkey, // 112
+    /// The position's index asset.
+    pub index_token_mint: Pubkey, // 144
+
+    /// The escrowed collateral, in native token amount.
+    pub collateral: u64, // 152
+    /// The size to open or increase the position by, denominated in USD.
+    pub size: u64, // 160
+    /// The slot at which the request was made.
+    pub slot: u64, // 168
+    /// The fee paid to the keeper executing the request, in lamports.
+    pub execution_fee: u64, // 176
+}
+
+impl PositionRequest {
+    /// Initializes the request.
+    pub fn init(
+        &mut self,
+        self_address: Pubkey,
+        authority: Pubkey,
+        token_mint: Pubkey,
+        index_token_mint: Pubkey,
+        bump_seed: u8,
+        direction: Direction,
+    ) {
+        self.self_address = self_address;
+        self.authority = authority;
+        self.token_mint = token_mint;
+        self.index_token_mint = index_token_mint;
+        self.bump_seed = [bump_seed];
+        self.direction = direction;
+    }
+
+    /// Gets the request signer seeds, the request being the authority of its token account.
+    pub fn signer_seeds(&self) -> [&[u8]; 5] {
+        use crate::constants::B_POSITION_REQUEST;
+        [
+            B_POSITION_REQUEST,
+            self.authority.as_ref(),
+            self.index_token_mint.as_ref(),
+            self.token_mint.as_ref(),
+            &self.bump_seed,
+        ]
+    }
+
+    /// Whether the request has timed out at the given slot, after which it can no longer be
+    /// executed and can be cancelled.
+    pub fn is_timed_out(&self, slot: u64) -> bool {
+        slot > self.slot + POSITION_REQUEST_TIMEOUT_SLOTS
+    }
+}
@@ -2,12 +2,21 @@ use anchor_lang::prelude::*;
 use jet_proto_proc_macros::assert_size;
 
 use crate::{
-    constants::{BASIS_POINTS_DIVISOR, SECONDS_IN_HOUR},
+    constants::{
+        BASIS_POINTS_DIVISOR, DEFAULT_FUNDING_BASE_RATE, DEFAULT_FUNDING_INDEX_PRECISION,
+        DEFAULT_FUNDING_KINK_UTILISATION_BPS, DEFAULT_FUNDING_SLOPE_ABOVE_KINK,
+        DEFAULT_FUNDING_SLOPE_BELOW_KINK, DEFAULT_MAX_FUNDING_RATE_PER_HOUR,
+        DEFAULT_STABLE_FUNDING_BASE_RATE, DEFAULT_STABLE_FUNDING_KINK_UTILISATION_BPS,
+        DEFAULT_STABLE_FUNDING_SLOPE_ABOVE_KINK, DEFAULT_STABLE_FUNDING_SLOPE_BELOW_KINK,
+    },
     error::ErrorCode,
-    utils::{get_funding_fee, get_position_fee, price::get_next_average_price, usd_to_token},
+    utils::{
+        get_funding_index_delta, get_kinked_funding_rate, get_skew_funding_deltas,
+        get_utilisation_bps, price::get_next_average_price,
+    },
 };
 
-use super::{Cache, Config};
+use super::{position::Direction, Cache, PriceFeedResult, PythOracleInfo, SwitchboardOracleInfo};
 
 #[derive(Debug, Clone, Copy, AnchorSerialize, AnchorDeserialize)]
 pub enum OracleType {
@@ -23,13 +32,16 @@ impl Default for OracleType {
     }
 }
 
-#[assert_size(aligns, 160)]
+#[assert_size(aligns, 320)]
 #[account]
+#[derive(Default)]
 #[repr(C)]
 pub struct VaultCache {
     /// The type of oracle price feed.
     pub oracle_type: OracleType, // 1
-    padding: [u8; 15], // 16
+    /// Whether the oracle of the other type is used as a secondary oracle.
+    pub has_secondary_oracle: bool, // 2
+    padding: [u8; 14], // 16
 
     /// The authority.
     pub authority: Pubkey, // 48
@@ -53,6 +65,32 @@ pub struct VaultCache {
     pub oracle_price: u64, // 152
     /// The funding index.
     pub funding_index: u64, // 160
+    /// The lower bound of the oracle price.
+    pub min_price: u64, // 168
+    /// The higher bound of the oracle price.
+    pub max_price: u64, // 176
+
+    /// The cumulative skew funding index of long positions, negative when they receive funding.
+    /// Both skew funding indices are scaled by the vault's funding index precision.
+    pub cumulative_long_skew_funding: i128, // 192
+    /// The cumulative skew funding index of short positions, negative when they receive funding.
+    pub cumulative_short_skew_funding: i128, // 208
+
+    /// The Pyth oracle, used when the oracle type is Pyth or as the secondary oracle.
+    pub pyth_oracle: PythOracleInfo, // 248
+    /// The Switchboard V2 oracle, used when the oracle type is Switchboard V2 or as the secondary
+    /// oracle.
+    pub switchboard_oracle: SwitchboardOracleInfo, // 288
+    /// The slot at which the oracle price was published.
+    pub oracle_price_slot: u64, // 296
+
+    /// The maximum long open interest, denominated in USD, zero when it is not capped.
+    pub max_long_open_interest: u64, // 304
+    /// The maximum short open interest, denominated in USD, zero when it is not capped.
+    pub max_short_open_interest: u64, // 312
+    /// The maximum deviation between the prices of the oracle and of the secondary oracle,
+    /// represented in basis points, zero when it is not checked.
+    pub max_oracle_deviation_bps: u64, // 320
 }
 
 impl VaultCache {
@@ -62,6 +100,84 @@ impl VaultCache {
         self.vault = vault;
     }
 
+    /// Gets the oracle account of the given oracle type.
+    pub fn get_oracle_account_of_type(&self, oracle_type: OracleType) -> Pubkey {
+        match oracle_type {
+            OracleType::Pyth => self.pyth_oracle.price_account,
+            OracleType::SwitchboardV2 => self.switchboard_oracle.aggregator_account,
+        }
+    }
+
+    /// Gets the oracle account of the vault's oracle type.
+    pub fn get_oracle_account(&self) -> Pubkey {
+        self.get_oracle_account_of_type(self.oracle_type)
+    }
+
+    /// Gets the type of the secondary oracle, which is the other oracle type.
+    pub fn get_secondary_oracle_type(&self) -> OracleType {
+        match self.oracle_type {
+            OracleType::Pyth => OracleType::SwitchboardV2,
+            OracleType::SwitchboardV2 => OracleType::Pyth,
+        }
+    }
+
+    /// Gets the secondary oracle account, which is the default address when the vault has no
+    /// secondary oracle, that of the system program.
+    pub fn get_secondary_oracle_account(&self) -> Pubkey {
+        if self.has_secondary_oracle {
+            self.get_oracle_account_of_type(self.get_secondary_oracle_type())
+        } else {
+            Pubkey::default()
+        }
+    }
+
+    /// Sets the oracle price and its bounds from a price feed result, along with the slot
+    /// at which it was published.
+    pub fn set_oracle_price(
+        &mut self,
+        price_feed_result: PriceFeedResult,
+        slot: u64,
+    ) -> Result<()> {
+        let (price, lower_bound, higher_bound) = match price_feed_result {
+            PriceFeedResult::Confident(price) => (price, price, price),
+            PriceFeedResult::Bounded(bounded_price) => (
+                bounded_price.price,
+                bounded_price.lower_bound,
+                bounded_price.higher_bound,
+            ),
+        };
+        self.oracle_price = price.try_into().or(Err(ErrorCode::InvalidOracleFeed))?;
+        self.min_price = lower_bound
+            .try_into()
+            .or(Err(ErrorCode::InvalidOracleFeed))?;
+        self.max_price = higher_bound
+            .try_into()
+            .or(Err(ErrorCode::InvalidOracleFeed))?;
+        self.oracle_price_slot = slot;
+        Ok(())
+    }
+
+    /// Gets the higher bound of the oracle price when maximising, otherwise the lower bound.
+    pub fn get_price(&self, maximise: bool) -> u64 {
+        if maximise {
+            self.max_price
+        } else {
+            self.min_price
+        }
+    }
+
+    /// Gets the price at which positions of the given direction are entered or increased, which is
+    /// the bound unfavourable to the trader: the higher bound for longs and the lower for shorts.
+    pub fn get_entry_price(&self, direction: Direction) -> u64 {
+        self.get_price(direction == Direction::Long)
+    }
+
+    /// Gets the price at which positions of the given direction are decreased or closed, which is
+    /// the bound unfavourable to the trader: the lower bound for longs and the higher for shorts.
+    pub fn get_exit_price(&self, direction: Direction) -> u64 {
+        self.get_price(direction == Direction::Short)
+    }
+
     /// Get the next average entry price for long positions.
     ///
     /// If this gets called we know for a fact that this is a long position being opened
@@ -71,7 +187,7 @@ impl VaultCache {
             self.long_open_interest,
             self.long_avg_entry_price,
             long_position_delta,
-            self.oracle_price,
+            self.get_entry_price(Direction::Long),
         )?;
 
         self.long_avg_entry_price = next_average_price;
@@ -89,7 +205,7 @@ impl VaultCache {
             self.short_open_interest,
             self.short_avg_entry_price,
             short_position_delta,
-            self.oracle_price,
+            self.get_entry_price(Direction::Short),
         )?;
 
         self.short_avg_entry_price = next_average_price;
@@ -97,6 +213,33 @@ impl VaultCache {
         Ok(next_average_price)
     }
 
+    /// Gets the cumulative skew funding index of the given side, in funding rate precision.
+    pub fn get_skew_funding_index(
+        &self,
+        direction: Direction,
+        funding_index_precision: u32,
+    ) -> i128 {
+        let index = match direction {
+            Direction::Long => self.cumulative_long_skew_funding,
+            Direction::Short => self.cumulative_short_skew_funding,
+        };
+        index / funding_index_precision as i128
+    }
+
+    /// Rescales the cumulative skew funding indices to a new funding index precision.
+    pub fn rescale_skew_funding(&mut self, precision: u32, next_precision: u32) {
+        self.cumulative_long_skew_funding =
+            self.cumulative_long_skew_funding * next_precision as i128 / precision as i128;
+        self.cumulative_short_skew_funding =
+            self.cumulative_short_skew_funding * next_precision as i128 / precision as i128;
+    }
+
+    /// Accrues skew funding to the cumulative indices of both sides.
+    pub fn accrue_skew_funding(&mut self, long_delta: i128, short_delta: i128) {
+        self.cumulative_long_skew_funding += long_delta;
+        self.cumulative_short_skew_funding += short_delta;
+    }
+
     /// Decreases the long open interest.
     pub fn decrease_long_open_interest(&mut self, amount: u64) {
         self.long_open_interest = self.long_open_interest.checked_sub(amount as u128).unwrap();
@@ -109,10 +252,36 @@ impl VaultCache {
             .checked_sub(amount as u128)
             .unwrap();
     }
+
+    /// Sets the maximum open interest of both sides, denominated in USD.
+    pub fn set_max_open_interest(
+        &mut self,
+        max_long_open_interest: u64,
+        max_short_open_interest: u64,
+    ) {
+        self.max_long_open_interest = max_long_open_interest;
+        self.max_short_open_interest = max_short_open_interest;
+    }
+
+    /// Sets the maximum deviation between the prices of the oracle and of the secondary oracle,
+    /// represented in basis points.
+    pub fn set_max_oracle_deviation_bps(&mut self, max_oracle_deviation_bps: u64) {
+        self.max_oracle_deviation_bps = max_oracle_deviation_bps;
+    }
+
+    /// Whether the open interest of the given side exceeds its maximum.
+    pub fn exceeds_max_open_interest(&self, direction: Direction) -> bool {
+        let (open_interest, max_open_interest) = match direction {
+            Direction::Long => (self.long_open_interest, self.max_long_open_interest),
+            Direction::Short => (self.short_open_interest, self.max_short_open_interest),
+        };
+        max_open_interest != 0 && open_interest > max_open_interest.into()
+    }
 }
 
 #[assert_size(aligns, 288)]
 #[account]
+#[derive(Default)]
 #[repr(C)]
 pub struct Vault {
     /// The vault signer seed bump.
@@ -125,7 +294,13 @@ pub struct Vault {
     pub cache_index: u8, // 4
     /// The decimals of the underlying token.
     pub decimals: u8, // 5
-    padding: [u8; 7], // 12
+    /// Whether the side with the larger open interest pays funding to the other side.
+    pub has_skew_funding: bool, // 6
+    /// Whether positions can only be entered or increased through a [`PositionRequest`].
+    pub has_position_requests: bool, // 7
+    padding: [u8; 1], // 8
+    /// The precision of the cumulative funding indices, on top of the funding rate precision.
+    pub funding_index_precision: u32, // 12
     /// The maximum allowed leverage for this vault, represented in basis points.
     pub max_leverage: u32, // 16
 
@@ -144,7 +319,7 @@ pub struct Vault {
     pub deposits: u128, // 192
     /// The amount of assets reserved for position.
     pub reserved: u128, // 208
-    /// The funding rate index.
+    /// The funding rate index, scaled by the funding index precision.
     pub cumulative_funding_rate: u128, // 224
     /// The debt amount on the vault.
     pub debt_amount: u128, // 240
@@ -157,7 +332,23 @@ pub struct Vault {
     pub guaranteed_usd: u128, // 256
     /// The timestamp of the last funding update.
     pub last_funding_update: u64, // 264
-    padding2: [u64; 3], // 288
+    /// The funding rate per hour at zero utilisation.
+    pub funding_base_rate: u32, // 268
+    /// The funding rate per hour added over the full utilisation range, below the kink.
+    pub funding_slope_below_kink: u32, // 272
+    /// The funding rate per hour added over the full utilisation range, above the kink.
+    pub funding_slope_above_kink: u32, // 276
+    /// The utilisation at which the funding rate starts growing with the slope above the kink,
+    /// represented in basis points.
+    pub funding_kink_utilisation_bps: u16, // 278
+    padding2: [u8; 2], // 280
+    /// The skew funding rate per hour when all of the open interest is on one side.
+    pub skew_funding_rate: u32, // 284
+    /// The maximum funding rate per hour, zero when the funding rate is not capped.
+    ///
+    /// Only the utilisation funding rate is capped, the skew funding rate is the most the heavier
+    /// side pays on its own.
+    pub max_funding_rate_per_hour: u32, // 288
 }
 
 impl Vault {
@@ -173,31 +364,160 @@ impl Vault {
 
     /// Gets the target debt amount for this vault.
     pub fn get_target_debt_amount(&self, cache: &Cache) -> u128 {
-        if cache.lp_token_supply == 0 {
+        let total_token_weights = cache.get_total_token_weights();
+        if cache.lp_token_supply == 0 || total_token_weights == 0 {
             return 0;
         }
         let weight = cache.get_weight(self.cache_index as usize);
-        cache
-            .lp_token_supply
-            .checked_mul(weight)
-            .and_then(|n| n.checked_div(cache.get_total_token_weights()))
+        (cache.lp_token_supply as u128)
+            .checked_mul(weight.into())
+            .and_then(|n| n.checked_div(total_token_weights.into()))
             .unwrap()
-            .into()
     }
 
-    /// Gets the next funding rate.
+    /// Gets the utilisation of the vault, represented in basis points.
+    pub fn get_utilisation_bps(&self) -> u64 {
+        get_utilisation_bps(self.reserved, self.deposits)
+    }
+
+    /// Gets the funding rate per hour at the vault's current utilisation, capped at the maximum.
+    pub fn get_funding_rate_per_hour(&self) -> u128 {
+        let rate = get_kinked_funding_rate(
+            self.get_utilisation_bps(),
+            self.funding_base_rate,
+            self.funding_slope_below_kink,
+            self.funding_kink_utilisation_bps,
+            self.funding_slope_above_kink,
+        );
+        if self.max_funding_rate_per_hour == 0 {
+            return rate;
+        }
+        rate.min(self.max_funding_rate_per_hour.into())
+    }
+
+    /// Gets the precision of the cumulative funding indices.
+    ///
+    /// Vaults that predate it have no precision set, which is the same as no extra precision.
+    pub fn get_funding_index_precision(&self) -> u32 {
+        self.funding_index_precision.max(1)
+    }
+
+    /// Gets the funding index in funding rate precision, which is what positions keep track of.
+    pub fn get_funding_index(&self) -> u64 {
+        self.cumulative_funding_rate
+            .checked_div(self.get_funding_index_precision().into())
+            .unwrap() as u64
+    }
+
+    /// Gets the number of seconds elapsed since the last funding update.
+    ///
+    /// Nothing has elapsed before the first update, which only starts the accrual.
+    pub fn get_elapsed_funding_seconds(&self, unix_timestamp: u64) -> u64 {
+        if self.last_funding_update == 0 {
+            return 0;
+        }
+        unix_timestamp.saturating_sub(self.last_funding_update)
+    }
+
+    /// Gets the next funding rate, scaled by the funding index precision.
     pub fn get_next_funding_rate(&self, unix_timestamp: u64) -> u128 {
-        let intervals = unix_timestamp
-            .checked_sub(self.last_funding_update)
-            .and_then(|n| n.checked_div(SECONDS_IN_HOUR))
-            .unwrap();
-        let funding_rate_factor: u128 = 100_000; // TODO:
-        let funding_rate = funding_rate_factor
-            .checked_mul(self.reserved)
-            .and_then(|n| n.checked_mul(intervals.into()))
-            .and_then(|n| n.checked_div(self.deposits))
+        get_funding_index_delta(
+            self.get_funding_rate_per_hour(),
+            self.get_elapsed_funding_seconds(unix_timestamp),
+            self.get_funding_index_precision(),
+        )
+    }
+
+    /// Gets the next deltas of the long and short skew funding indices.
+    ///
+    /// Both are zero unless the vault has skew funding enabled. The skew funding rate is exempt
+    /// from the maximum funding rate, which caps the utilisation funding rate alone: the skew
+    /// funding is paid between the two sides rather than to the pool, and the heavier side never
+    /// pays more than the skew funding rate.
+    pub fn get_next_skew_funding_deltas(
+        &self,
+        vault_cache: &VaultCache,
+        unix_timestamp: u64,
+    ) -> (i128, i128) {
+        if !self.has_skew_funding {
+            return (0, 0);
+        }
+        let index_delta = get_funding_index_delta(
+            self.skew_funding_rate.into(),
+            self.get_elapsed_funding_seconds(unix_timestamp),
+            self.get_funding_index_precision(),
+        );
+        get_skew_funding_deltas(
+            vault_cache.long_open_interest,
+            vault_cache.short_open_interest,
+            index_delta,
+        )
+    }
+
+    /// Sets the funding rate parameters.
+    pub fn set_funding_params(
+        &mut self,
+        base_rate: u32,
+        slope_below_kink: u32,
+        kink_utilisation_bps: u16,
+        slope_above_kink: u32,
+        max_rate_per_hour: u32,
+    ) {
+        self.funding_base_rate = base_rate;
+        self.funding_slope_below_kink = slope_below_kink;
+        self.funding_kink_utilisation_bps = kink_utilisation_bps;
+        self.funding_slope_above_kink = slope_above_kink;
+        self.max_funding_rate_per_hour = max_rate_per_hour;
+    }
+
+    /// Sets the precision of the cumulative funding indices.
+    ///
+    /// The indices are rescaled to the new precision, positions are unaffected since they keep
+    /// track of the indices in funding rate precision.
+    pub fn set_funding_index_precision(&mut self, vault_cache: &mut VaultCache, precision: u32) {
+        let current_precision = self.get_funding_index_precision();
+        self.cumulative_funding_rate = self
+            .cumulative_funding_rate
+            .checked_mul(precision.into())
+            .and_then(|n| n.checked_div(current_precision.into()))
             .unwrap();
-        funding_rate
+        vault_cache.rescale_skew_funding(current_precision, precision);
+        self.funding_index_precision = precision;
+    }
+
+    /// Sets the skew funding parameters.
+    pub fn set_skew_funding_params(&mut self, has_skew_funding: bool, skew_funding_rate: u32) {
+        self.has_skew_funding = has_skew_funding;
+        self.skew_funding_rate = skew_funding_rate;
+    }
+
+    /// Sets whether positions can only be entered or increased through a [`PositionRequest`].
+    pub fn set_has_position_requests(&mut self, has_position_requests: bool) {
+        self.has_position_requests = has_position_requests;
+    }
+
+    /// Sets the default funding rate parameters, which depend on whether the vault is stable.
+    ///
+    /// This is meant for new vaults, since the funding index precision is set without rescaling.
+    pub fn set_default_funding_params(&mut self) {
+        if self.is_stable {
+            self.set_funding_params(
+                DEFAULT_STABLE_FUNDING_BASE_RATE,
+                DEFAULT_STABLE_FUNDING_SLOPE_BELOW_KINK,
+                DEFAULT_STABLE_FUNDING_KINK_UTILISATION_BPS,
+                DEFAULT_STABLE_FUNDING_SLOPE_ABOVE_KINK,
+                DEFAULT_MAX_FUNDING_RATE_PER_HOUR,
+            );
+        } else {
+            self.set_funding_params(
+                DEFAULT_FUNDING_BASE_RATE,
+                DEFAULT_FUNDING_SLOPE_BELOW_KINK,
+                DEFAULT_FUNDING_KINK_UTILISATION_BPS,
+                DEFAULT_FUNDING_SLOPE_ABOVE_KINK,
+                DEFAULT_MAX_FUNDING_RATE_PER_HOUR,
+            );
+        }
+        self.funding_index_precision = DEFAULT_FUNDING_INDEX_PRECISION;
     }
 
     /// Initialize the vault.
@@ -227,6 +547,16 @@ impl Vault {
         Ok(())
     }
 
+    /// Gets the liquidity that can be reserved or paid out of the vault, in native token amount.
+    ///
+    /// The collateral of long positions is part of the pool while it is still held in the
+    /// positions' escrows, so only the deposits actually held by the vault's token account count.
+    pub fn get_available_liquidity(&self, token_vault_amount: u64) -> u128 {
+        self.deposits
+            .min(token_vault_amount.into())
+            .saturating_sub(self.reserved)
+    }
+
     /// Increases the pool amount.
     pub fn increase_pool_amount(&mut self, amount: u128) {
         self.deposits += amount;
@@ -234,7 +564,7 @@ impl Vault {
 
     /// Decreases the pool amount.
     pub fn decrease_pool_amount(&mut self, amount: u128) -> Result<()> {
-        self.deposits = self.reserved.checked_sub(amount).unwrap();
+        self.deposits = self.deposits.checked_sub(amount).unwrap();
         Ok(())
     }
 
@@ -260,41 +590,15 @@ impl Vault {
         Ok(())
     }
 
-    pub fn get_utilisation(&self) -> f64 {
-        if self.deposits == 0 {
-            return 0f64;
-        }
-        self.reserved.checked_div(self.deposits).unwrap() as f64
-    }
-
-    /// Updates the funding rate if enough time has passed in order for it to be updated.
+    /// Accrues the funding rate for the time elapsed since the last update.
     pub fn update_funding_rate(&mut self, clock: &Clock) -> Result<()> {
         let unix_timestamp: u64 = clock
             .unix_timestamp
             .try_into()
             .or(Err(ErrorCode::InvalidTimestampConversion))?;
-        if self.last_funding_update + SECONDS_IN_HOUR <= unix_timestamp {
-            self.cumulative_funding_rate =
-                self.cumulative_funding_rate + self.get_next_funding_rate(unix_timestamp);
-            self.last_funding_update = unix_timestamp;
-        }
+        self.cumulative_funding_rate =
+            self.cumulative_funding_rate + self.get_next_funding_rate(unix_timestamp);
+        self.last_funding_update = unix_timestamp;
         Ok(())
     }
-
-    pub fn collect_margin_fees(
-        &mut self,
-        config: &Config,
-        vault_cache: &VaultCache,
-        size_delta: u64,
-        size: u64,
-        funding_rate: u64,
-    ) -> Result<u128> {
-        let mut fee = get_position_fee(config, size_delta);
-        fee += get_funding_fee(&self, size, funding_rate);
-
-        let underlying_fee = usd_to_token(fee.into(), vault_cache.oracle_price, self.decimals)?;
-        // todo: add fee
-
-        Ok(underlying_fee)
-    }
 }
@@ -0,0 +1,226 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{position::Direction, Cache, Vault, VaultCache},
+};
+
+use super::{get_pnl, refresh_oracle_price, token_to_usd};
+
+/// Loads the vaults registered in the [`Cache`] from the given accounts.
+///
+/// The accounts are expected as `[vault, vault_cache, oracle, secondary_oracle]` quadruples, in the
+/// same order the vaults are registered in the cache, this way the AUM can never be computed over a
+/// subset of the pool.
+/// The oracle prices of the loaded caches are refreshed from their bound oracle accounts.
+pub fn load_vaults<'info>(
+    cache: &Cache,
+    accounts: &[AccountInfo<'info>],
+    clock: &Clock,
+) -> Result<Vec<(Account<'info, Vault>, Account<'info, VaultCache>)>> {
+    let vaults = cache.get_vaults();
+    require!(
+        !vaults.is_empty() && accounts.len() == vaults.len() * 4,
+        ErrorCode::InvalidVaultAccounts
+    );
+
+    let mut loaded_vaults = Vec::with_capacity(vaults.len());
+    for (vault_address, accounts) in vaults.iter().zip(accounts.chunks(4)) {
+        let vault: Account<Vault> = Account::try_from(&accounts[0])?;
+        let mut vault_cache: Account<VaultCache> = Account::try_from(&accounts[1])?;
+        require!(
+            vault.key() == *vault_address && vault_cache.vault == vault.key(),
+            ErrorCode::InvalidVaultAccounts
+        );
+        refresh_oracle_price(&mut vault_cache, &accounts[2], &accounts[3], clock)?;
+        loaded_vaults.push((vault, vault_cache));
+    }
+    Ok(loaded_vaults)
+}
+
+/// The pool's assets under management, denominated in USD.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Aum {
+    /// The AUM valued at the lower bound of each vault's oracle price.
+    pub min: u128,
+    /// The AUM valued at the higher bound of each vault's oracle price.
+    pub max: u128,
+}
+
+/// Applies the unrealized PnL of traders to the given AUM.
+///
+/// Trader profits are owed by the pool while trader losses are owed to the pool.
+fn apply_trader_pnl(aum: u128, has_profit: bool, delta: u128) -> u128 {
+    if has_profit {
+        aum.saturating_sub(delta)
+    } else {
+        aum + delta
+    }
+}
+
+/// Calculates the pool's AUM, denominated in USD.
+///
+/// Stable vaults are valued at their deposits. Non-stable vaults are valued at their unreserved
+/// deposits plus the USD guaranteed by open long positions, with the unrealized PnL of short positions
+/// being owed to the pool when traders are at a loss and owed by the pool otherwise.
+fn calculate_aum(vaults: &[(&Vault, &VaultCache)], maximise: bool) -> Result<u128> {
+    let mut aum = 0u128;
+    let mut short_profits = 0u128;
+
+    for (vault, vault_cache) in vaults.iter() {
+        let price = vault_cache.get_price(maximise);
+
+        if vault.is_stable {
+            aum += token_to_usd(vault.deposits, price, vault.decimals)?;
+            continue;
+        }
+
+        let (has_profit, delta) = get_pnl(
+            vault_cache.short_open_interest,
+            vault_cache.short_avg_entry_price,
+            price,
+            Direction::Short,
+        );
+        if has_profit {
+            short_profits += delta;
+        } else {
+            aum = apply_trader_pnl(aum, has_profit, delta);
+        }
+
+        // the reserved tokens can exceed the deposits once payouts are taken out of the pool
+        let unreserved_deposits = vault.deposits.saturating_sub(vault.reserved);
+        aum += vault.guaranteed_usd;
+        aum += token_to_usd(unreserved_deposits, price, vault.decimals)?;
+    }
+
+    // the profits are only applied once every vault is valued, as they are owed by the whole pool
+    Ok(apply_trader_pnl(aum, true, short_profits))
+}
+
+/// Gets the pool's AUM at both the lower and higher bound of each vault's oracle price.
+pub fn get_aum(vaults: &[(Account<Vault>, Account<VaultCache>)]) -> Result<Aum> {
+    let vaults: Vec<(&Vault, &VaultCache)> = vaults
+        .iter()
+        .map(|(vault, vault_cache)| (&**vault, &**vault_cache))
+        .collect();
+    let vaults = &vaults[..];
+    Ok(Aum {
+        min: calculate_aum(vaults, false)?,
+        max: calculate_aum(vaults, true)?,
+    })
+}
+
+/// Gets the amount of LP tokens to mint for a deposit of the given USD value.
+///
+/// The first deposit into the pool mints LP tokens one to one with its USD value,
+/// after that, LP tokens are minted pro-rata to the pool's AUM.
+pub fn get_lp_mint_amount(usd_amount: u128, aum: u128, lp_token_supply: u64) -> u128 {
+    if aum == 0 || lp_token_supply == 0 {
+        return usd_amount;
+    }
+    usd_amount
+        .checked_mul(lp_token_supply.into())
+        .and_then(|n| n.checked_div(aum))
+        .unwrap()
+}
+
+/// Gets the USD value redeemable for the given amount of LP tokens.
+pub fn get_lp_redemption_amount(lp_amount: u64, aum: u128, lp_token_supply: u64) -> u128 {
+    (lp_amount as u128)
+        .checked_mul(aum)
+        .and_then(|n| n.checked_div(lp_token_supply.into()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_get_lp_mint_amount() -> Result<()> {
+        // the first deposit mints at one LP token per USD
+        assert_eq!(get_lp_mint_amount(1_000_000_000, 0, 0), 1_000_000_000);
+
+        // an AUM of 2_000 USD over 1_000 LP tokens prices each LP token at 2 USD
+        let lp_amount = get_lp_mint_amount(100_000_000, 2_000_000_000, 1_000_000_000);
+        assert_eq!(lp_amount, 50_000_000);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_lp_redemption_amount() -> Result<()> {
+        // an AUM of 2_000 USD over 1_000 LP tokens prices each LP token at 2 USD
+        let usd_amount = get_lp_redemption_amount(50_000_000, 2_000_000_000, 1_000_000_000);
+        assert_eq!(usd_amount, 100_000_000);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_lp_rounding() -> Result<()> {
+        // both minting and redeeming round down in favour of the pool
+        assert_eq!(get_lp_mint_amount(1, 3_000_000, 1_000_000), 0);
+        assert_eq!(get_lp_redemption_amount(1, 2_000_000, 3_000_000), 0);
+
+        // depositing and redeeming right away never returns more than was deposited
+        let lp_amount = get_lp_mint_amount(1_000_000, 3_000_000, 1_000_000);
+        assert_eq!(lp_amount, 333_333);
+        let usd_amount = get_lp_redemption_amount(lp_amount as u64, 4_000_000, 1_333_333);
+        assert_eq!(usd_amount, 999_999);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_apply_trader_pnl() -> Result<()> {
+        assert_eq!(apply_trader_pnl(1_000, true, 300), 700);
+        assert_eq!(apply_trader_pnl(1_000, false, 300), 1_300);
+        // the pool can never be worth less than nothing
+        assert_eq!(apply_trader_pnl(1_000, true, 1_300), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_calculate_aum() -> Result<()> {
+        // 1_000 USDC priced between 0.99 and 1.01 USD
+        let mut stable_vault = Vault::default();
+        stable_vault.is_stable = true;
+        stable_vault.decimals = 6;
+        stable_vault.deposits = 1_000_000_000;
+        let mut stable_vault_cache = VaultCache::default();
+        stable_vault_cache.min_price = 9_900_000_000;
+        stable_vault_cache.max_price = 10_100_000_000;
+
+        // 10 SOL priced between 19 and 21 USD, of which 4 are reserved, with 50 USD guaranteed
+        // by longs and 100 USD of shorts entered at 20 USD
+        let mut vault = Vault::default();
+        vault.decimals = 9;
+        vault.deposits = 10_000_000_000;
+        vault.reserved = 4_000_000_000;
+        vault.guaranteed_usd = 50_000_000;
+        let mut vault_cache = VaultCache::default();
+        vault_cache.min_price = 190_000_000_000;
+        vault_cache.max_price = 210_000_000_000;
+        vault_cache.short_open_interest = 100_000_000;
+        vault_cache.short_avg_entry_price = 200_000_000_000;
+
+        let vaults = [(&stable_vault, &stable_vault_cache), (&vault, &vault_cache)];
+
+        // 990 USD of USDC, 50 USD guaranteed and 114 USD of unreserved SOL, less the 5 USD the
+        // shorts are in profit
+        assert_eq!(calculate_aum(&vaults, false)?, 1_149_000_000);
+        // 1_010 USD of USDC, 50 USD guaranteed and 126 USD of unreserved SOL, plus the 5 USD the
+        // shorts are at a loss
+        assert_eq!(calculate_aum(&vaults, true)?, 1_191_000_000);
+
+        // no unreserved SOL is valued once more than the deposits are reserved
+        vault.reserved = 12_000_000_000;
+        let vaults = [(&stable_vault, &stable_vault_cache), (&vault, &vault_cache)];
+        assert_eq!(calculate_aum(&vaults, false)?, 1_035_000_000);
+        assert_eq!(calculate_aum(&vaults, true)?, 1_065_000_000);
+
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
+use anchor_lang::prelude::*;
+
 use crate::{
-    constants::BASIS_POINTS_DIVISOR,
+    constants::{BASIS_POINTS_DIVISOR, FUNDING_RATE_PRECISION},
+    error::ErrorCode,
     state::{Cache, Config, Vault},
 };
 
@@ -19,18 +22,30 @@ pub fn get_position_fee(config: &Config, size_delta: u64) -> u64 {
     size_delta.checked_sub(after_fee).unwrap()
 }
 
-/// Gets the funding fee for a given position
+/// Gets the amount left after applying a fee, denominated in basis points.
+pub fn get_amount_after_fee(amount: u128, fee_bps: u64) -> Result<u128> {
+    let remaining_bps = BASIS_POINTS_DIVISOR
+        .checked_sub(fee_bps)
+        .ok_or(ErrorCode::InvalidFee)?;
+    let amount_after_fee = amount
+        .checked_mul(remaining_bps.into())
+        .ok_or(ErrorCode::MathOverflow)?
+        / BASIS_POINTS_DIVISOR as u128;
+    Ok(amount_after_fee)
+}
+
+/// Gets the funding fee for a given position, denominated in USD.
 pub fn get_funding_fee(vault: &Vault, size: u64, funding_rate: u64) -> u64 {
     if size == 0 {
         return 0;
     }
 
-    let funding_rate = vault
-        .cumulative_funding_rate
-        .checked_sub(funding_rate.into())
-        .unwrap();
+    let funding_rate = vault.get_funding_index().checked_sub(funding_rate).unwrap() as u128;
 
-    let funding_fee = funding_rate.checked_mul(size.into()).unwrap();
+    let funding_fee = funding_rate
+        .checked_mul(size.into())
+        .and_then(|n| n.checked_div(FUNDING_RATE_PRECISION.into()))
+        .unwrap();
     funding_fee as u64
 }
 
@@ -98,3 +113,78 @@ pub fn get_fee_bps(
         .unwrap();
     fee_bps as u64 + tax_bps as u64
 }
+
+/// Gets the fee to apply for a swap between two vaults, denominated in basis points.
+///
+/// Swaps between two stable vaults use the stable fee and tax. The tax is applied according to
+/// how much the swap moves each vault away from its target weight, the highest fee of both vaults
+/// being the one that is charged.
+pub fn get_swap_fee_bps(
+    config: &Config,
+    cache: &Cache,
+    vault_in: &Vault,
+    vault_out: &Vault,
+    usd_amount: u128,
+) -> u64 {
+    let (fee_bps, tax_bps) = if vault_in.is_stable && vault_out.is_stable {
+        (config.stable_swap_fee_bps, config.stable_tax_bps)
+    } else {
+        (config.swap_fee_bps, config.tax_bps)
+    };
+    let fee_bps_in = get_fee_bps(cache, vault_in, usd_amount, fee_bps, tax_bps, true);
+    let fee_bps_out = get_fee_bps(cache, vault_out, usd_amount, fee_bps, tax_bps, false);
+    fee_bps_in.max(fee_bps_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_get_amount_after_fee() -> Result<()> {
+        assert_eq!(get_amount_after_fee(1_000_000, 30)?, 997_000);
+        assert_eq!(get_amount_after_fee(1_000_000, 10_000)?, 0);
+        // rounds down in favour of the pool
+        assert_eq!(get_amount_after_fee(999, 30)?, 996);
+        assert!(get_amount_after_fee(1_000_000, 10_001).is_err());
+        assert!(get_amount_after_fee(u128::MAX, 30).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_fee_bps_with_token_weights() -> Result<()> {
+        let mut cache = Cache::default();
+        cache.lp_token_supply = 1_000_000_000;
+        cache.vault_count = 2;
+        cache.set_token_weight(0, 1)?;
+        cache.set_token_weight(1, 3)?;
+        assert_eq!(cache.get_total_token_weights(), 4);
+        // updating a weight keeps the total in sync
+        cache.set_token_weight(1, 3_000)?;
+        cache.set_token_weight(0, 1_000)?;
+        assert_eq!(cache.get_total_token_weights(), 4_000);
+
+        // the vault targets a quarter of the pool
+        let mut vault = Vault::default();
+        vault.has_dynamic_fees = true;
+        vault.cache_index = 0;
+        vault.debt_amount = 250_000_000;
+        assert_eq!(vault.get_target_debt_amount(&cache), 250_000_000);
+
+        // moving away from the target is taxed on the average distance to it
+        let fee_bps = get_fee_bps(&cache, &vault, 100_000_000, 30, 50, true);
+        assert_eq!(fee_bps, 40);
+
+        // moving towards the target is rebated on the initial distance to it
+        vault.debt_amount = 350_000_000;
+        let fee_bps = get_fee_bps(&cache, &vault, 100_000_000, 30, 50, false);
+        assert_eq!(fee_bps, 10);
+
+        // the base fee applies without dynamic fees
+        vault.has_dynamic_fees = false;
+        let fee_bps = get_fee_bps(&cache, &vault, 100_000_000, 30, 50, true);
+        assert_eq!(fee_bps, 30);
+
+        Ok(())
+    }
+}
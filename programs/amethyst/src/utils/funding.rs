@@ -0,0 +1,231 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    constants::{BASIS_POINTS_DIVISOR, FUNDING_RATE_PRECISION, SECONDS_IN_HOUR},
+    error::ErrorCode,
+    state::vault::{Vault, VaultCache},
+};
+
+/// Gets the utilisation of a vault, represented in basis points.
+pub fn get_utilisation_bps(reserved: u128, deposits: u128) -> u64 {
+    if deposits == 0 {
+        return 0;
+    }
+    reserved
+        .checked_mul(BASIS_POINTS_DIVISOR.into())
+        .and_then(|n| n.checked_div(deposits))
+        .unwrap()
+        .min(BASIS_POINTS_DIVISOR.into()) as u64
+}
+
+/// Gets the funding rate per hour for the given utilisation, represented in basis points.
+///
+/// The rate grows from the base rate with the slope below the kink until the utilisation reaches
+/// the kink, and with the slope above the kink after that. The slopes are the rate added over the
+/// full utilisation range.
+pub fn get_kinked_funding_rate(
+    utilisation_bps: u64,
+    base_rate: u32,
+    slope_below_kink: u32,
+    kink_utilisation_bps: u16,
+    slope_above_kink: u32,
+) -> u128 {
+    let kink_utilisation_bps = kink_utilisation_bps as u64;
+    let bps = BASIS_POINTS_DIVISOR as u128;
+    let utilisation_below_kink = utilisation_bps.min(kink_utilisation_bps) as u128;
+    let utilisation_above_kink = utilisation_bps.saturating_sub(kink_utilisation_bps) as u128;
+
+    base_rate as u128
+        + utilisation_below_kink * slope_below_kink as u128 / bps
+        + utilisation_above_kink * slope_above_kink as u128 / bps
+}
+
+/// Gets the delta of a cumulative funding index accruing the given rate per hour over the given
+/// number of seconds, scaled by the funding index precision.
+pub fn get_funding_index_delta(
+    rate_per_hour: u128,
+    elapsed_seconds: u64,
+    funding_index_precision: u32,
+) -> u128 {
+    rate_per_hour
+        .checked_mul(elapsed_seconds.into())
+        .and_then(|n| n.checked_mul(funding_index_precision.into()))
+        .and_then(|n| n.checked_div(SECONDS_IN_HOUR.into()))
+        .unwrap()
+}
+
+/// Gets the deltas of the long and short skew funding indices.
+///
+/// The side with the larger open interest pays a share of the given index delta proportional to
+/// the imbalance between both sides, which is all of it when all of the open interest is on one
+/// side. What it pays is shared by the other side, whose index decreases accordingly.
+pub fn get_skew_funding_deltas(
+    long_open_interest: u128,
+    short_open_interest: u128,
+    index_delta: u128,
+) -> (i128, i128) {
+    let total_open_interest = long_open_interest + short_open_interest;
+    if total_open_interest == 0 || long_open_interest == short_open_interest {
+        return (0, 0);
+    }
+    let (heavy_open_interest, light_open_interest) = if long_open_interest > short_open_interest {
+        (long_open_interest, short_open_interest)
+    } else {
+        (short_open_interest, long_open_interest)
+    };
+
+    let paid_rate = (heavy_open_interest - light_open_interest)
+        .checked_mul(index_delta)
+        .and_then(|n| n.checked_div(total_open_interest))
+        .unwrap();
+    // without open interest on the light side the payments accrue to the pool
+    let received_rate = if light_open_interest == 0 {
+        0
+    } else {
+        paid_rate
+            .checked_mul(heavy_open_interest)
+            .and_then(|n| n.checked_div(light_open_interest))
+            .unwrap()
+    };
+
+    let paid_rate = paid_rate as i128;
+    let received_rate = received_rate as i128;
+    if long_open_interest > short_open_interest {
+        (paid_rate, -received_rate)
+    } else {
+        (-received_rate, paid_rate)
+    }
+}
+
+/// Gets the skew funding accrued by a position of the given size since the given index,
+/// denominated in USD and negative when the position receives funding.
+pub fn get_skew_funding_fee(
+    size: u64,
+    skew_funding_index: i128,
+    last_skew_funding_index: i128,
+) -> i128 {
+    (skew_funding_index - last_skew_funding_index)
+        .checked_mul(size.into())
+        .and_then(|n| n.checked_div(FUNDING_RATE_PRECISION.into()))
+        .unwrap()
+}
+
+/// Updates the [`Vault`]'s funding rate along with the skew funding indices of its [`VaultCache`].
+///
+/// The skew funding has to accrue before the funding rate is updated, since both are accrued over
+/// the intervals elapsed since the last funding update. The maximum funding rate of the vault only
+/// caps its funding rate, the skew funding accrues at the uncapped skew funding rate.
+pub fn update_funding(
+    vault: &mut Vault,
+    vault_cache: &mut VaultCache,
+    clock: &Clock,
+) -> Result<()> {
+    let unix_timestamp: u64 = clock
+        .unix_timestamp
+        .try_into()
+        .or(Err(ErrorCode::InvalidTimestampConversion))?;
+    let (long_delta, short_delta) = vault.get_next_skew_funding_deltas(vault_cache, unix_timestamp);
+    vault_cache.accrue_skew_funding(long_delta, short_delta);
+    vault.update_funding_rate(clock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_get_utilisation_bps() -> Result<()> {
+        assert_eq!(get_utilisation_bps(0, 0), 0);
+        assert_eq!(get_utilisation_bps(1, 3), 3_333);
+        assert_eq!(get_utilisation_bps(3, 3), 10_000);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_kinked_funding_rate() -> Result<()> {
+        let base_rate = 10;
+        let slope_below_kink = 100;
+        let kink_utilisation_bps = 8_000;
+        let slope_above_kink = 1_000;
+
+        let rate = get_kinked_funding_rate(
+            4_000,
+            base_rate,
+            slope_below_kink,
+            kink_utilisation_bps,
+            slope_above_kink,
+        );
+        assert_eq!(rate, 50);
+
+        // above the kink the rate grows ten times faster
+        let rate = get_kinked_funding_rate(
+            9_000,
+            base_rate,
+            slope_below_kink,
+            kink_utilisation_bps,
+            slope_above_kink,
+        );
+        assert_eq!(rate, 190);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_funding_index_delta() -> Result<()> {
+        assert_eq!(get_funding_index_delta(100, 3_600, 1), 100);
+        // a single second accrues with enough precision
+        assert_eq!(get_funding_index_delta(100, 1, 1), 0);
+        assert_eq!(get_funding_index_delta(100, 1, 1_000_000), 27_777);
+        assert_eq!(get_funding_index_delta(100, 5_400, 1_000_000), 150_000_000);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_skew_funding_deltas() -> Result<()> {
+        assert_eq!(get_skew_funding_deltas(0, 0, 1_000), (0, 0));
+        assert_eq!(get_skew_funding_deltas(100, 100, 1_000), (0, 0));
+
+        // longs pay half the rate, which shorts share at three times the rate
+        assert_eq!(get_skew_funding_deltas(300, 100, 1_000), (500, -1_500));
+        assert_eq!(get_skew_funding_deltas(100, 300, 2_000), (-3_000, 1_000));
+
+        // without shorts the longs still pay the full rate
+        assert_eq!(get_skew_funding_deltas(300, 0, 1_000), (1_000, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_skew_funding_fee() -> Result<()> {
+        assert_eq!(get_skew_funding_fee(1_000_000_000, 500, 0), 500_000);
+        assert_eq!(get_skew_funding_fee(1_000_000_000, -1_500, 0), -1_500_000);
+        assert_eq!(get_skew_funding_fee(1_000_000_000, 0, 0), 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_update_funding_does_not_cap_skew_funding() -> Result<()> {
+        let mut vault = Vault::default();
+        vault.deposits = 1_000;
+        vault.reserved = 1_000;
+        vault.funding_base_rate = 500;
+        vault.max_funding_rate_per_hour = 100;
+        vault.has_skew_funding = true;
+        vault.skew_funding_rate = 1_000;
+        vault.last_funding_update = 1;
+        let mut vault_cache = VaultCache::default();
+        vault_cache.long_open_interest = 300;
+        vault_cache.short_open_interest = 0;
+
+        let mut clock = Clock::default();
+        clock.unix_timestamp = 3_601;
+        update_funding(&mut vault, &mut vault_cache, &clock)?;
+
+        // the funding rate is capped, the longs pay the full skew funding rate over the hour
+        assert_eq!(vault.cumulative_funding_rate, 100);
+        assert_eq!(vault_cache.cumulative_long_skew_funding, 1_000);
+        assert_eq!(vault_cache.cumulative_short_skew_funding, 0);
+
+        Ok(())
+    }
+}
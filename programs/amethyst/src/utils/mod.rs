@@ -1,12 +1,20 @@
+pub mod aum;
 pub mod conversions;
 pub mod fees;
+pub mod funding;
 pub mod oracle;
+pub mod position;
 pub mod price;
+pub mod swap;
 
+pub use aum::*;
 pub use conversions::*;
 pub use fees::*;
+pub use funding::*;
 pub use oracle::*;
+pub use position::*;
 pub use price::*;
+pub use swap::*;
 
 use anchor_lang::prelude::*;
 
@@ -1,14 +1,11 @@
 use crate::{
-    constants::{
-        ORACLE_PRICE_FEED_TTL_SECS, ORACLE_PRICE_TARGET_EXPONENT, PYTH_FEED_DECIMALS,
-        SWITCHBOARD_FEED_DECIMALS, SWITCHBOARD_FEED_EXPONENT,
-    },
+    constants::{BASIS_POINTS_DIVISOR, ORACLE_PRICE_FEED_TTL_SECS, ORACLE_PRICE_TARGET_EXPONENT},
     error::ErrorCode,
-    state::{BoundedPrice, PriceFeedResult},
+    events::OraclePriceUpdated,
+    state::{BoundedPrice, OracleType, PriceFeedResult, VaultCache},
 };
 use anchor_lang::prelude::*;
-use pyth_sdk_solana::PriceFeed;
-use std::ops::{Div, Mul};
+use pyth_sdk_solana::{load_price_feed_from_account_info, state::load_price_account, PriceFeed};
 use switchboard_v2::{AggregatorAccountData, SwitchboardDecimal};
 
 fn get_target_exponent(base_decimals: i32, current_expo: i32, target_expo: i32) -> Result<i32> {
@@ -51,33 +48,47 @@ pub fn scale_amount(
 }
 
 /// Scales a given price from current exponent to target exponent.
-pub fn scale_price(
-    unscaled_price: f64,
-    base_decimals: i32,
-    current_expo: i32,
-    target_expo: i32,
-) -> Result<u128> {
-    // scale the price to the target decimals
-    let target_decimals = get_target_exponent(base_decimals, current_expo, target_expo)?;
-    let decimal_adj = 10u64.pow(target_decimals.abs() as u32) as f64;
+///
+/// The price is scaled with integer arithmetic only and rounded down when scaling it down.
+/// Negative prices are rejected and prices overflowing when scaled up are invalid.
+pub fn scale_price(unscaled_price: i128, current_expo: i32, target_expo: i32) -> Result<u128> {
+    require!(unscaled_price >= 0, ErrorCode::NegativeOraclePrice);
+    let unscaled_price = unscaled_price as u128;
+
+    let expo_delta = current_expo
+        .checked_sub(target_expo)
+        .ok_or(ErrorCode::InvalidOracleFeed)?;
+    let decimal_adj = 10u128.checked_pow(expo_delta.unsigned_abs());
 
     #[cfg(test)]
     msg!(
-        "Target Decimals: {} - Decimals Adj.: {}",
-        target_decimals,
+        "Exponent Delta: {} - Decimals Adj.: {:?}",
+        expo_delta,
         decimal_adj
     );
 
-    let scaled_price = if target_decimals < 0 {
-        unscaled_price.div(decimal_adj)
+    let scaled_price = if expo_delta < 0 {
+        // a divisor which does not fit in a u128 is larger than any price
+        decimal_adj.map_or(0, |adj| unscaled_price / adj)
     } else {
-        unscaled_price.mul(decimal_adj)
+        decimal_adj
+            .and_then(|adj| unscaled_price.checked_mul(adj))
+            .ok_or(ErrorCode::InvalidOracleFeed)?
     };
 
     #[cfg(test)]
     msg!("Scaled Price: {}", scaled_price);
 
-    Ok(scaled_price as u128)
+    Ok(scaled_price)
+}
+
+/// Scales a [`SwitchboardDecimal`] to the oracle price target exponent.
+fn scale_switchboard_decimal(decimal: &SwitchboardDecimal) -> Result<u128> {
+    let current_expo = i32::try_from(decimal.scale)
+        .map_err(|_| error!(ErrorCode::InvalidOracleFeed))?
+        .checked_neg()
+        .ok_or(ErrorCode::InvalidOracleFeed)?;
+    scale_price(decimal.mantissa, current_expo, ORACLE_PRICE_TARGET_EXPONENT)
 }
 
 /// Gets an asset's from a Switchboard [`AggregatorAccountData`] and applies bounds if necessary.
@@ -86,30 +97,15 @@ pub fn get_switchboard_price(
     max_confidence_threshold: f64,
     unix_timestamp: i64,
 ) -> Result<PriceFeedResult> {
-    let price_result: f64 = aggregator_account.get_result()?.try_into()?;
+    let price_result = aggregator_account.get_result()?;
 
     aggregator_account
         .check_staleness(unix_timestamp, ORACLE_PRICE_FEED_TTL_SECS as i64)
         .map_err(|_| error!(ErrorCode::StaleOracleFeed))?;
 
-    let std_deviation: f64 = aggregator_account
-        .latest_confirmed_round
-        .std_deviation
-        .try_into()?;
-
-    let price_result = scale_price(
-        price_result,
-        SWITCHBOARD_FEED_DECIMALS,
-        SWITCHBOARD_FEED_EXPONENT,
-        ORACLE_PRICE_TARGET_EXPONENT,
-    )?;
-
-    let std_deviation = scale_price(
-        std_deviation,
-        SWITCHBOARD_FEED_DECIMALS,
-        SWITCHBOARD_FEED_EXPONENT,
-        ORACLE_PRICE_TARGET_EXPONENT,
-    )?;
+    let price_result = scale_switchboard_decimal(&price_result)?;
+    let std_deviation =
+        scale_switchboard_decimal(&aggregator_account.latest_confirmed_round.std_deviation)?;
 
     msg!(
         "Price Feed Result: {} - Std. Deviation: {}",
@@ -151,18 +147,8 @@ pub fn get_pyth_price(
             return Err(ErrorCode::StaleOracleFeed.into());
         }
     };
-    let price_result = scale_price(
-        price.price as f64,
-        PYTH_FEED_DECIMALS,
-        price.expo,
-        ORACLE_PRICE_TARGET_EXPONENT,
-    )?;
-    let std_deviation = scale_price(
-        price.conf as f64,
-        PYTH_FEED_DECIMALS,
-        price.expo,
-        ORACLE_PRICE_TARGET_EXPONENT,
-    )?;
+    let price_result = scale_price(price.price.into(), price.expo, ORACLE_PRICE_TARGET_EXPONENT)?;
+    let std_deviation = scale_price(price.conf.into(), price.expo, ORACLE_PRICE_TARGET_EXPONENT)?;
 
     msg!(
         "Price Feed Result: {} - Std. Deviation: {}",
@@ -188,6 +174,159 @@ pub fn get_pyth_price(
     }
 }
 
+/// Gets an asset's price from the given oracle account of the given [`OracleType`], according to
+/// the [`VaultCache`]'s confidence interval thresholds.
+pub fn get_oracle_price(
+    vault_cache: &VaultCache,
+    oracle_type: OracleType,
+    oracle_account: &AccountInfo,
+    unix_timestamp: i64,
+) -> Result<PriceFeedResult> {
+    match oracle_type {
+        OracleType::Pyth => {
+            let price_feed = load_price_feed_from_account_info(oracle_account)
+                .map_err(|_| error!(ErrorCode::InvalidOracleFeed))?;
+            get_pyth_price(
+                &price_feed,
+                vault_cache.pyth_oracle.max_confidence_interval,
+                unix_timestamp,
+            )
+        }
+        OracleType::SwitchboardV2 => {
+            let aggregator_account = AggregatorAccountData::new(oracle_account)?;
+            get_switchboard_price(
+                &aggregator_account,
+                vault_cache.switchboard_oracle.max_confidence_interval,
+                unix_timestamp,
+            )
+        }
+    }
+}
+
+/// Gets the slot at which the price of the given oracle account of the given [`OracleType`] was
+/// published.
+pub fn get_oracle_publish_slot(
+    oracle_type: OracleType,
+    oracle_account: &AccountInfo,
+) -> Result<u64> {
+    match oracle_type {
+        OracleType::Pyth => {
+            let data = oracle_account.try_borrow_data()?;
+            let price_account =
+                load_price_account(&data).map_err(|_| error!(ErrorCode::InvalidOracleFeed))?;
+            Ok(price_account.agg.pub_slot)
+        }
+        OracleType::SwitchboardV2 => {
+            let aggregator_account = AggregatorAccountData::new(oracle_account)?;
+            Ok(aggregator_account.latest_confirmed_round.round_open_slot)
+        }
+    }
+}
+
+/// Whether the price deviates from the reference price by more than the maximum deviation,
+/// represented in basis points, which is not checked when zero.
+pub fn exceeds_max_deviation(price: u128, reference_price: u128, max_deviation_bps: u64) -> bool {
+    max_deviation_bps != 0
+        && price
+            .abs_diff(reference_price)
+            .saturating_mul(BASIS_POINTS_DIVISOR.into())
+            > reference_price.saturating_mul(max_deviation_bps.into())
+}
+
+/// Selects the price of a vault out of its oracle's and its secondary oracle's, if any.
+///
+/// The oracle's price is used unless it is unavailable, e.g. stale, or it is out of confidence
+/// while the secondary oracle's is not, in which case the secondary oracle's price is used.
+/// When both prices are available they must not deviate by more than the maximum deviation.
+/// Returns the selected price along with whether it is the secondary oracle's.
+pub fn select_oracle_price(
+    price: Result<PriceFeedResult>,
+    secondary_price: Option<Result<PriceFeedResult>>,
+    max_deviation_bps: u64,
+) -> Result<(PriceFeedResult, bool)> {
+    match (price, secondary_price) {
+        (price, None) => price.map(|price| (price, false)),
+        (Ok(price), Some(Ok(secondary_price))) => {
+            require!(
+                !exceeds_max_deviation(secondary_price.price(), price.price(), max_deviation_bps),
+                ErrorCode::OracleDeviationExceeded
+            );
+            match (&price, &secondary_price) {
+                (PriceFeedResult::Bounded(_), PriceFeedResult::Confident(_)) => {
+                    Ok((secondary_price, true))
+                }
+                _ => Ok((price, false)),
+            }
+        }
+        (Ok(price), Some(Err(_))) => Ok((price, false)),
+        (Err(_), Some(Ok(secondary_price))) => Ok((secondary_price, true)),
+        (Err(err), Some(Err(_))) => Err(err),
+    }
+}
+
+/// Refreshes the [`VaultCache`]'s oracle price from the given oracle accounts, which must be the
+/// oracle and secondary oracle accounts bound to the cache.
+///
+/// The secondary oracle is only read when the vault has one, the price is then selected as per
+/// [`select_oracle_price`]. The oracle the price was read from is recorded in the event log.
+pub fn refresh_oracle_price<'info>(
+    vault_cache: &mut VaultCache,
+    oracle_account: &AccountInfo<'info>,
+    secondary_oracle_account: &AccountInfo<'info>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(
+        oracle_account.key() == vault_cache.get_oracle_account(),
+        ErrorCode::OracleAccountMismatch
+    );
+    require!(
+        secondary_oracle_account.key() == vault_cache.get_secondary_oracle_account(),
+        ErrorCode::OracleAccountMismatch
+    );
+
+    let oracle_type = vault_cache.oracle_type;
+    let secondary_oracle_type = vault_cache.get_secondary_oracle_type();
+    let price = get_oracle_price(
+        vault_cache,
+        oracle_type,
+        oracle_account,
+        clock.unix_timestamp,
+    );
+    let secondary_price = if vault_cache.has_secondary_oracle {
+        Some(get_oracle_price(
+            vault_cache,
+            secondary_oracle_type,
+            secondary_oracle_account,
+            clock.unix_timestamp,
+        ))
+    } else {
+        None
+    };
+    let (price_feed_result, is_secondary) =
+        select_oracle_price(price, secondary_price, vault_cache.max_oracle_deviation_bps)?;
+
+    let (oracle_type, oracle_account) = if is_secondary {
+        (secondary_oracle_type, secondary_oracle_account)
+    } else {
+        (oracle_type, oracle_account)
+    };
+    let publish_slot = get_oracle_publish_slot(oracle_type, oracle_account)?;
+    vault_cache.set_oracle_price(price_feed_result, publish_slot)?;
+
+    emit!(OraclePriceUpdated {
+        vault: vault_cache.vault,
+        oracle: oracle_account.key(),
+        oracle_type,
+        is_secondary,
+        price: vault_cache.oracle_price,
+        min_price: vault_cache.min_price,
+        max_price: vault_cache.max_price,
+        price_slot: publish_slot,
+    });
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use pyth_sdk_solana::state::{
@@ -203,9 +342,9 @@ mod tests {
     #[test]
     pub fn test_scale_pyth_price() -> Result<()> {
         let prices = [
-            2_292_133_500_000f64, // btcusd from pyth
-            159_405_889_054f64,   // ethusd from pyth
-            2_428_596_998f64,     // solusd from pyth
+            2_292_133_500_000i64, // btcusd from pyth
+            159_405_889_054i64,   // ethusd from pyth
+            2_428_596_998i64,     // solusd from pyth
         ];
         let current_exponents = [-8, -8, -8];
         let target_exponents = [-10, -10, -10];
@@ -216,9 +355,12 @@ mod tests {
         ];
 
         for (idx, price) in prices.iter().enumerate() {
-            let scaled_price =
-                scale_price(*price, 0, current_exponents[idx], target_exponents[idx])?;
-            assert_eq!(scaled_price as u128, results[idx]);
+            let scaled_price = scale_price(
+                (*price).into(),
+                current_exponents[idx],
+                target_exponents[idx],
+            )?;
+            assert_eq!(scaled_price, results[idx]);
         }
 
         Ok(())
@@ -227,23 +369,169 @@ mod tests {
     #[test]
     pub fn test_scale_switchboard_price() -> Result<()> {
         let prices = [
-            0.0000010379908497333, // bonk from sbv2
-            0.95275399047246,      // orca from sbv2
-            0.326177379,           // srm from sbv2
+            SwitchboardDecimal::new(10_379_908_497_333, 19), // bonk from sbv2
+            SwitchboardDecimal::new(95_275_399_047_246, 14), // orca from sbv2
+            SwitchboardDecimal::new(326_177_379, 9),         // srm from sbv2
         ];
-        let current_exponents = [-0, 0, 0];
-        let target_exponents = [-10, -10, -10];
         let results = [10_379, 9_527_539_904, 3_261_773_790];
 
         for (idx, price) in prices.iter().enumerate() {
-            let scaled_price =
-                scale_price(*price, 0, current_exponents[idx], target_exponents[idx])?;
+            let scaled_price = scale_switchboard_decimal(price)?;
             assert_eq!(scaled_price, results[idx]);
         }
 
         Ok(())
     }
 
+    #[test]
+    pub fn test_scale_price_extreme_exponents() -> Result<()> {
+        // no precision is lost on the largest pyth price
+        assert_eq!(
+            scale_price(i64::MAX.into(), -8, -10)?,
+            922_337_203_685_477_580_700
+        );
+        assert_eq!(scale_price(1, 28, -10)?, 10u128.pow(38));
+        assert_eq!(
+            scale_price(123_456_789_000_000_000_000_000_000_000_000, -40, -10)?,
+            123
+        );
+        // the divisor does not fit in a u128
+        assert_eq!(scale_price(i128::MAX, -60, -10)?, 0);
+        assert_eq!(scale_price(i128::MAX, i32::MIN + 10, -10)?, 0);
+
+        assert_eq!(
+            scale_switchboard_decimal(&SwitchboardDecimal::new(i128::MAX, 28))?,
+            i128::MAX as u128 / 10u128.pow(18)
+        );
+        assert_eq!(
+            scale_switchboard_decimal(&SwitchboardDecimal::new(1, 60))?,
+            0
+        );
+
+        // the scaled price does not fit in a u128
+        assert!(scale_price(i64::MAX.into(), 10, -10).is_err());
+        assert!(scale_price(1, 29, -10).is_err());
+        assert!(scale_price(1, i32::MAX, -10).is_err());
+        assert!(scale_switchboard_decimal(&SwitchboardDecimal::new(1, u32::MAX)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_scale_negative_price() -> Result<()> {
+        assert!(scale_price(-1, -8, -10).is_err());
+        assert!(scale_price(i64::MIN.into(), -8, -10).is_err());
+        assert!(scale_switchboard_decimal(&SwitchboardDecimal::new(-326_177_379, 9)).is_err());
+        assert_eq!(scale_price(0, -8, -10)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_exceeds_max_deviation() {
+        assert!(!exceeds_max_deviation(100, 100, 100));
+        assert!(!exceeds_max_deviation(101, 100, 100));
+        assert!(!exceeds_max_deviation(99, 100, 100));
+        assert!(exceeds_max_deviation(102, 100, 100));
+        assert!(exceeds_max_deviation(98, 100, 100));
+        // the deviation is not checked
+        assert!(!exceeds_max_deviation(u128::MAX, 100, 0));
+    }
+
+    #[test]
+    pub fn test_select_oracle_price() -> Result<()> {
+        let bounded = |price: u128| {
+            PriceFeedResult::Bounded(BoundedPrice {
+                price,
+                lower_bound: price - 1,
+                higher_bound: price + 1,
+            })
+        };
+        let stale = || Err(error!(ErrorCode::StaleOracleFeed));
+
+        // without a secondary oracle
+        assert_eq!(
+            select_oracle_price(Ok(bounded(100)), None, 100)?,
+            (bounded(100), false)
+        );
+        assert!(select_oracle_price(stale(), None, 100).is_err());
+
+        // the oracle is confident
+        assert_eq!(
+            select_oracle_price(
+                Ok(PriceFeedResult::Confident(100)),
+                Some(Ok(PriceFeedResult::Confident(101))),
+                100
+            )?,
+            (PriceFeedResult::Confident(100), false)
+        );
+        assert_eq!(
+            select_oracle_price(
+                Ok(PriceFeedResult::Confident(100)),
+                Some(Ok(bounded(101))),
+                100
+            )?,
+            (PriceFeedResult::Confident(100), false)
+        );
+        assert_eq!(
+            select_oracle_price(Ok(PriceFeedResult::Confident(100)), Some(stale()), 100)?,
+            (PriceFeedResult::Confident(100), false)
+        );
+
+        // the oracle is out of confidence
+        assert_eq!(
+            select_oracle_price(
+                Ok(bounded(100)),
+                Some(Ok(PriceFeedResult::Confident(99))),
+                100
+            )?,
+            (PriceFeedResult::Confident(99), true)
+        );
+        assert_eq!(
+            select_oracle_price(Ok(bounded(100)), Some(Ok(bounded(99))), 100)?,
+            (bounded(100), false)
+        );
+        assert_eq!(
+            select_oracle_price(Ok(bounded(100)), Some(stale()), 100)?,
+            (bounded(100), false)
+        );
+
+        // the oracle is stale
+        assert_eq!(
+            select_oracle_price(stale(), Some(Ok(PriceFeedResult::Confident(150))), 100)?,
+            (PriceFeedResult::Confident(150), true)
+        );
+        assert_eq!(
+            select_oracle_price(stale(), Some(Ok(bounded(150))), 100)?,
+            (bounded(150), true)
+        );
+        assert!(select_oracle_price(stale(), Some(stale()), 100).is_err());
+
+        // both oracles are live but deviate
+        assert!(select_oracle_price(
+            Ok(PriceFeedResult::Confident(100)),
+            Some(Ok(PriceFeedResult::Confident(102))),
+            100
+        )
+        .is_err());
+        assert!(select_oracle_price(
+            Ok(bounded(100)),
+            Some(Ok(PriceFeedResult::Confident(98))),
+            100
+        )
+        .is_err());
+        assert_eq!(
+            select_oracle_price(
+                Ok(bounded(100)),
+                Some(Ok(PriceFeedResult::Confident(150))),
+                0
+            )?,
+            (PriceFeedResult::Confident(150), true)
+        );
+
+        Ok(())
+    }
+
     #[test]
     pub fn test_get_pyth_price_confident() -> Result<()> {
         let price_account = PriceAccount {
@@ -0,0 +1,538 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    args::DecreasePositionArgs,
+    constants::BASIS_POINTS_DIVISOR,
+    error::ErrorCode,
+    state::{
+        position::{Direction, Position},
+        Cache, Config, Vault, VaultCache,
+    },
+};
+
+use super::{
+    get_funding_fee, get_next_average_price, get_pnl, get_position_fee, token_to_usd, usd_to_token,
+};
+
+/// Gets the collateral of a position after deducting its unrealized losses and the given fees,
+/// denominated in USD.
+///
+/// Unrealized profits are not accounted for, since they are only owed to the position once realized.
+/// Returns zero if the losses and fees exceed the collateral.
+pub fn get_remaining_collateral(
+    collateral: u64,
+    size: u64,
+    avg_entry_price: u64,
+    price: u64,
+    direction: Direction,
+    fees: u64,
+) -> u64 {
+    let (has_profit, delta) = get_pnl(size.into(), avg_entry_price, price, direction);
+    let loss = if has_profit { 0 } else { delta };
+    (collateral as u128)
+        .saturating_sub(loss)
+        .saturating_sub(fees.into()) as u64
+}
+
+/// How a position can be liquidated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidationState {
+    /// The position is healthy.
+    None,
+    /// The position exceeds the maximum leverage but is still above the maintenance margin,
+    /// which means that it can be decreased back to a safe leverage.
+    Partial,
+    /// The position is below the maintenance margin and has to be closed.
+    Full,
+}
+
+/// Gets the liquidation state of a position of the given size with the given remaining collateral.
+///
+/// The leverage is represented in basis points.
+pub fn get_liquidation_state(
+    size: u64,
+    remaining_collateral: u64,
+    maintenance_margin_bps: u16,
+    max_leverage: u32,
+) -> LiquidationState {
+    if remaining_collateral == 0 {
+        return LiquidationState::Full;
+    }
+    let maintenance_margin = (size as u128)
+        .checked_mul(maintenance_margin_bps.into())
+        .and_then(|n| n.checked_div(BASIS_POINTS_DIVISOR.into()))
+        .unwrap();
+    if (remaining_collateral as u128) < maintenance_margin {
+        return LiquidationState::Full;
+    }
+    let leverage = (size as u128)
+        .checked_mul(BASIS_POINTS_DIVISOR.into())
+        .and_then(|n| n.checked_div(remaining_collateral.into()))
+        .unwrap();
+    if leverage > max_leverage.into() {
+        LiquidationState::Partial
+    } else {
+        LiquidationState::None
+    }
+}
+
+/// Gets the size to close for a position to be brought back to the target leverage, denominated in USD.
+///
+/// The remaining collateral is expected net of the margin fee to close the whole position, which
+/// makes it unaffected by closing part of the position since the fee on the closed size is then
+/// charged out of the collateral and the fee on the rest still deducted. Returns the whole size if
+/// the target leverage cannot be reached.
+pub fn get_liquidation_size_delta(
+    size: u64,
+    remaining_collateral: u64,
+    target_leverage: u32,
+) -> u64 {
+    // the leverage after closing the size delta `d` must satisfy
+    // (size - d) * bps <= target_leverage * remaining_collateral
+    let max_size =
+        (target_leverage as u128) * (remaining_collateral as u128) / BASIS_POINTS_DIVISOR as u128;
+    (size as u128).saturating_sub(max_size) as u64
+}
+
+/// Validates that the liquidation target leverage is below the maximum leverage of every vault in
+/// the pool, whose accounts are expected in the same order the vaults are registered in the cache.
+pub fn validate_liquidation_target_leverage(
+    cache: &Cache,
+    accounts: &[AccountInfo],
+    liquidation_target_leverage: u32,
+) -> Result<()> {
+    let vaults = cache.get_vaults();
+    require!(
+        accounts.len() == vaults.len(),
+        ErrorCode::InvalidVaultAccounts
+    );
+    for (vault_address, account) in vaults.iter().zip(accounts) {
+        let vault: Account<Vault> = Account::try_from(account)?;
+        require!(
+            vault.key() == *vault_address,
+            ErrorCode::InvalidVaultAccounts
+        );
+        require!(
+            liquidation_target_leverage < vault.max_leverage,
+            ErrorCode::InvalidLiquidationTargetLeverage
+        );
+    }
+    Ok(())
+}
+
+/// The outcome of decreasing a position.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DecreaseResult {
+    /// The amount of tokens owed to the position's authority.
+    pub payout: u64,
+    /// The amount of tokens to release from the position's escrow.
+    pub released_collateral: u64,
+    /// Whether the realised PnL is a profit.
+    pub has_profit: bool,
+    /// The realised PnL, denominated in USD.
+    pub realised_pnl: u128,
+    /// The fees charged, denominated in USD.
+    pub fee: u128,
+}
+
+/// Decreases a position by the given size and collateral deltas, denominated in USD, at the index
+/// vault's exit price.
+///
+/// The PnL is realised pro-rata to the size delta, profits being paid out by the pool and losses
+/// being taken out of the collateral. The margin fee on the size delta and the accrued funding are
+/// paid out of the amount owed to the position's authority, or out of the collateral if that is not
+/// enough. Closing the whole size pays out the remaining collateral.
+///
+/// The collateral released from the escrow is pro-rata to the reduction of the collateral,
+/// it goes to the position's authority first and the rest goes to the vault. The vault holds the
+/// collateral, its tokens are valued at the given collateral price.
+pub fn decrease_position(
+    config: &Config,
+    vault: &mut Vault,
+    index_vault_cache: &mut VaultCache,
+    position: &mut Position,
+    args: &DecreasePositionArgs,
+    collateral_price: u64,
+    escrow_balance: u64,
+) -> Result<DecreaseResult> {
+    let size_delta = args.size_delta;
+    let collateral_delta = args.collateral_delta.unwrap_or_default();
+    require!(
+        size_delta <= position.size && (size_delta > 0 || collateral_delta > 0),
+        ErrorCode::InvalidSizeDelta
+    );
+    let price = index_vault_cache.get_exit_price(position.direction);
+    let is_closing = size_delta == position.size;
+    let fee = get_position_fee(config, size_delta) as u128
+        + get_funding_fee(vault, position.size, position.last_funding_index) as u128;
+
+    let (has_profit, delta) = get_pnl(
+        position.size.into(),
+        position.avg_entry_price,
+        price,
+        position.direction,
+    );
+    let realised_pnl = delta
+        .checked_mul(size_delta.into())
+        .and_then(|n| n.checked_div(position.size.into()))
+        .unwrap();
+
+    let collateral_before = position.collateral as u128;
+    let mut collateral = collateral_before;
+    let mut usd_out = 0u128;
+
+    if has_profit {
+        usd_out += realised_pnl;
+    } else {
+        require!(
+            collateral >= realised_pnl,
+            ErrorCode::InsufficientCollateralForLoss
+        );
+        collateral -= realised_pnl;
+    }
+
+    require!(
+        collateral >= collateral_delta.into(),
+        ErrorCode::InvalidTokenAmount
+    );
+    collateral -= collateral_delta as u128;
+    usd_out += collateral_delta as u128;
+
+    if is_closing {
+        usd_out += collateral;
+        collateral = 0;
+    }
+
+    if usd_out > fee {
+        usd_out -= fee;
+    } else {
+        require!(collateral >= fee, ErrorCode::InsufficientCollateralForFee);
+        collateral -= fee;
+    }
+
+    let collateral_reduction = collateral_before - collateral;
+    let released_collateral = if is_closing {
+        escrow_balance
+    } else {
+        (escrow_balance as u128)
+            .checked_mul(collateral_reduction)
+            .and_then(|n| n.checked_div(collateral_before))
+            .unwrap() as u64
+    };
+    let payout: u64 = usd_to_token(usd_out, collateral_price, vault.decimals)?
+        .try_into()
+        .or(Err(ErrorCode::InvalidTokenAmount))?;
+
+    let reserve_delta = (position.reserved_amount as u128)
+        .checked_mul(size_delta.into())
+        .and_then(|n| n.checked_div(position.size.into()))
+        .unwrap();
+    vault.decrease_reserved(reserve_delta.min(vault.reserved))?;
+
+    match position.direction {
+        Direction::Long => {
+            index_vault_cache.decrease_long_open_interest(size_delta);
+            // the guaranteed USD tracks the size of long positions net of their collateral
+            vault.increase_guaranteed_usd(collateral_reduction);
+            let guaranteed_usd_delta = (size_delta as u128).min(vault.guaranteed_usd);
+            vault.decrease_guaranteed_usd(guaranteed_usd_delta)?;
+        }
+        Direction::Short => {
+            index_vault_cache.decrease_short_open_interest(size_delta);
+            // unlike for longs, the collateral of short positions is not part of the pool yet
+            vault.increase_pool_amount(released_collateral.into());
+        }
+    }
+    vault.decrease_pool_amount(payout.into())?;
+
+    position.collateral = collateral as u64;
+    position.size -= size_delta;
+    position.reserved_amount -= reserve_delta as u64;
+    position.last_funding_index = vault.get_funding_index();
+
+    Ok(DecreaseResult {
+        payout,
+        released_collateral,
+        has_profit,
+        realised_pnl,
+        fee,
+    })
+}
+
+/// Reserves the collateral tokens needed to cover the given size, denominated in USD, from the
+/// vault, whose collateral tokens are valued at the given collateral price.
+fn reserve_liquidity(
+    vault: &mut Vault,
+    token_vault_amount: u64,
+    size: u64,
+    collateral_price: u64,
+) -> Result<u64> {
+    let reserved = usd_to_token(size.into(), collateral_price, vault.decimals)?;
+    require!(
+        vault.get_available_liquidity(token_vault_amount) >= reserved,
+        ErrorCode::InsufficientLiquidityToEnterPosition
+    );
+    vault.increase_reserved(reserved);
+    Ok(reserved as u64)
+}
+
+/// Opens a freshly initialized position with the given collateral, in native token amount, and
+/// size, denominated in USD, at the index vault's entry price.
+///
+/// The collateral tokens, valued at the given collateral price, must already be in the position's
+/// escrow. The tokens needed to cover the size are reserved from the vault's available liquidity,
+/// given the amount held by its token account, while the open interest is tracked by the index
+/// vault's cache, which caps it.
+pub fn open_position(
+    vault: &mut Vault,
+    token_vault_amount: u64,
+    index_vault_cache: &mut VaultCache,
+    position: &mut Position,
+    collateral: u64,
+    size: u64,
+    collateral_price: u64,
+) -> Result<()> {
+    let collateral_usd = token_to_usd(collateral.into(), collateral_price, vault.decimals)?;
+    require!(
+        collateral_usd > 0 && collateral_usd <= size.into(),
+        ErrorCode::InvalidSizeDelta
+    );
+    let reserved = reserve_liquidity(vault, token_vault_amount, size, collateral_price)?;
+
+    position.collateral = collateral_usd as u64;
+    position.size = size;
+    position.avg_entry_price = index_vault_cache.get_entry_price(position.direction);
+    position.reserved_amount = reserved;
+
+    match position.direction {
+        Direction::Long => {
+            index_vault_cache.get_next_long_average_entry_price(size)?;
+            // the collateral of long positions is treated as part of the pool
+            vault.increase_guaranteed_usd((size as u128) - collateral_usd);
+            vault.increase_pool_amount(collateral.into());
+        }
+        Direction::Short => {
+            index_vault_cache.get_next_short_average_entry_price(size)?;
+        }
+    }
+    require!(
+        !index_vault_cache.exceeds_max_open_interest(position.direction),
+        ErrorCode::MaxOpenInterestExceeded
+    );
+
+    Ok(())
+}
+
+/// The outcome of increasing a position.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct IncreaseResult {
+    /// The collateral added to the position, denominated in USD.
+    pub collateral_delta_usd: u128,
+    /// The margin fee charged, denominated in USD.
+    pub fee: u64,
+    /// The amount of tokens to release from the position's escrow to pay the fee.
+    pub fee_token_amount: u64,
+}
+
+/// Increases a position by the given collateral delta, in native token amount, and size delta,
+/// denominated in USD, at the index vault's entry price.
+///
+/// The collateral tokens, valued at the given collateral price, must already be in the position's
+/// escrow. The margin fee on the size delta is paid out of the collateral, its tokens are to be
+/// released from the escrow to the vault by the caller. The tokens needed to cover the size delta
+/// are reserved from the vault's available liquidity, given the amount held by its token account,
+/// while the open interest is tracked by the index vault's cache, which caps it.
+pub fn increase_position(
+    config: &Config,
+    vault: &mut Vault,
+    token_vault_amount: u64,
+    index_vault_cache: &mut VaultCache,
+    position: &mut Position,
+    collateral_delta: u64,
+    size_delta: u64,
+    collateral_price: u64,
+) -> Result<IncreaseResult> {
+    let decimals = vault.decimals;
+    let collateral_delta_usd = token_to_usd(collateral_delta.into(), collateral_price, decimals)?;
+    let fee = get_position_fee(config, size_delta);
+    let fee_token_amount: u64 = usd_to_token(fee.into(), collateral_price, decimals)?
+        .try_into()
+        .or(Err(ErrorCode::InvalidTokenAmount))?;
+    let collateral = position.collateral as u128 + collateral_delta_usd;
+    require!(
+        collateral > fee.into(),
+        ErrorCode::InsufficientCollateralForFee
+    );
+    let reserve_delta = reserve_liquidity(vault, token_vault_amount, size_delta, collateral_price)?;
+
+    position.avg_entry_price = get_next_average_price(
+        position.size.into(),
+        position.avg_entry_price,
+        size_delta,
+        index_vault_cache.get_entry_price(position.direction),
+    )?;
+    position.collateral = (collateral - fee as u128) as u64;
+    position.size += size_delta;
+    position.reserved_amount += reserve_delta;
+
+    match position.direction {
+        Direction::Long => {
+            index_vault_cache.get_next_long_average_entry_price(size_delta)?;
+            // the guaranteed USD tracks the size of long positions net of their collateral
+            vault.increase_guaranteed_usd(size_delta as u128 + fee as u128);
+            let guaranteed_usd_delta = collateral_delta_usd.min(vault.guaranteed_usd);
+            vault.decrease_guaranteed_usd(guaranteed_usd_delta)?;
+            // the collateral of long positions is treated as part of the pool
+            vault.increase_pool_amount(collateral_delta.into());
+        }
+        Direction::Short => {
+            index_vault_cache.get_next_short_average_entry_price(size_delta)?;
+            // unlike for longs, the collateral of short positions is not part of the pool yet
+            vault.increase_pool_amount(fee_token_amount.into());
+        }
+    }
+    require!(
+        !index_vault_cache.exceeds_max_open_interest(position.direction),
+        ErrorCode::MaxOpenInterestExceeded
+    );
+
+    Ok(IncreaseResult {
+        collateral_delta_usd,
+        fee,
+        fee_token_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_get_remaining_collateral() -> Result<()> {
+        let collateral = 1_000_000_000; // 1_000 USD
+        let size = 10_000_000_000; // 10_000 USD
+        let avg_entry_price = 200_000_000_000; // 20 USD
+
+        // a 5% move against the position wipes out half of the collateral
+        let remaining = get_remaining_collateral(
+            collateral,
+            size,
+            avg_entry_price,
+            190_000_000_000,
+            Direction::Long,
+            10_000_000,
+        );
+        assert_eq!(remaining, 490_000_000);
+
+        // profits are not added to the collateral
+        let remaining = get_remaining_collateral(
+            collateral,
+            size,
+            avg_entry_price,
+            190_000_000_000,
+            Direction::Short,
+            10_000_000,
+        );
+        assert_eq!(remaining, 990_000_000);
+
+        // losses exceeding the collateral leave nothing
+        let remaining = get_remaining_collateral(
+            collateral,
+            size,
+            avg_entry_price,
+            240_000_000_000,
+            Direction::Short,
+            0,
+        );
+        assert_eq!(remaining, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_liquidation_state() -> Result<()> {
+        let size = 10_000_000_000; // 10_000 USD
+        let maintenance_margin_bps = 300; // 3%
+        let max_leverage = 200_000; // 20x
+
+        let state =
+            get_liquidation_state(size, 1_000_000_000, maintenance_margin_bps, max_leverage);
+        assert_eq!(state, LiquidationState::None);
+
+        // 25x leverage is over the maximum, but above the 300 USD maintenance margin
+        let state = get_liquidation_state(size, 400_000_000, maintenance_margin_bps, max_leverage);
+        assert_eq!(state, LiquidationState::Partial);
+
+        // below the 300 USD maintenance margin
+        let state = get_liquidation_state(size, 250_000_000, maintenance_margin_bps, max_leverage);
+        assert_eq!(state, LiquidationState::Full);
+
+        let state = get_liquidation_state(size, 0, 0, max_leverage);
+        assert_eq!(state, LiquidationState::Full);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_get_liquidation_size_delta() -> Result<()> {
+        let size = 10_000_000_000; // 10_000 USD
+        let remaining_collateral = 400_000_000; // 400 USD
+        let target_leverage = 200_000; // 20x
+
+        // 2_000 USD have to be closed for the position to be at 20x
+        let size_delta = get_liquidation_size_delta(size, remaining_collateral, target_leverage);
+        assert_eq!(size_delta, 2_000_000_000);
+
+        // a healthy position does not need to be decreased
+        let size_delta = get_liquidation_size_delta(size, 1_000_000_000, target_leverage);
+        assert_eq!(size_delta, 0);
+
+        // the whole position is closed when there is no collateral left
+        let size_delta = get_liquidation_size_delta(size, 0, target_leverage);
+        assert_eq!(size_delta, size);
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_partial_liquidation_band() -> Result<()> {
+        let size = 10_000_000_000; // 10_000 USD
+        let maintenance_margin_bps = 300; // 3%
+        let max_leverage = 200_000; // 20x
+        let target_leverage = 150_000; // 15x
+
+        // 25x leverage is over the maximum, but above the 300 USD maintenance margin
+        let remaining_collateral = 400_000_000; // 400 USD
+        let state = get_liquidation_state(
+            size,
+            remaining_collateral,
+            maintenance_margin_bps,
+            max_leverage,
+        );
+        assert_eq!(state, LiquidationState::Partial);
+
+        // the position is brought back to 15x, since the remaining collateral already accounts
+        // for the fee to close the whole position it is the same after the decrease
+        let size_delta = get_liquidation_size_delta(size, remaining_collateral, target_leverage);
+        assert_eq!(size_delta, 4_000_000_000);
+        let state = get_liquidation_state(
+            size - size_delta,
+            remaining_collateral,
+            maintenance_margin_bps,
+            max_leverage,
+        );
+        assert_eq!(state, LiquidationState::None);
+
+        // right at the maintenance margin the position is still partially liquidated
+        let state = get_liquidation_state(size, 300_000_000, maintenance_margin_bps, max_leverage);
+        assert_eq!(state, LiquidationState::Partial);
+        let size_delta = get_liquidation_size_delta(size, 300_000_000, target_leverage);
+        assert_eq!(size_delta, 5_500_000_000);
+
+        // just below it the position is fully liquidated
+        let state = get_liquidation_state(size, 299_999_999, maintenance_margin_bps, max_leverage);
+        assert_eq!(state, LiquidationState::Full);
+
+        Ok(())
+    }
+}
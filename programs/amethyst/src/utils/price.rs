@@ -1,6 +1,6 @@
 use std::ops::Add;
 
-use crate::error::ErrorCode;
+use crate::{error::ErrorCode, state::position::Direction};
 use anchor_lang::prelude::*;
 
 pub fn get_next_average_price(
@@ -20,6 +20,30 @@ pub fn get_next_average_price(
         .or(Err(ErrorCode::InvalidAveragePrice.into()))
 }
 
+/// Gets the profit or loss of a position of the given size at the given price.
+///
+/// Returns whether the position is in profit along with the absolute value of the delta,
+/// which is denominated in the same unit as the position size.
+pub fn get_pnl(size: u128, avg_entry_price: u64, price: u64, direction: Direction) -> (bool, u128) {
+    if size == 0 || avg_entry_price == 0 {
+        return (false, 0);
+    }
+    let price_delta = if price > avg_entry_price {
+        price - avg_entry_price
+    } else {
+        avg_entry_price - price
+    };
+    let delta = size
+        .checked_mul(price_delta.into())
+        .and_then(|n| n.checked_div(avg_entry_price.into()))
+        .unwrap();
+    let has_profit = match direction {
+        Direction::Long => price > avg_entry_price,
+        Direction::Short => avg_entry_price > price,
+    };
+    (has_profit, delta)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,4 +58,24 @@ mod tests {
         assert_eq!(next_average_price, 10_200_002_000);
         Ok(())
     }
+
+    #[test]
+    pub fn test_get_pnl() -> Result<()> {
+        let size = 10_000_000_000; // 10_000 USD
+        let avg_entry_price = 200_000_000_000; // 20 USD
+
+        let long_profit = get_pnl(size, avg_entry_price, 220_000_000_000, Direction::Long);
+        assert_eq!(long_profit, (true, 1_000_000_000));
+
+        let long_loss = get_pnl(size, avg_entry_price, 180_000_000_000, Direction::Long);
+        assert_eq!(long_loss, (false, 1_000_000_000));
+
+        let short_profit = get_pnl(size, avg_entry_price, 180_000_000_000, Direction::Short);
+        assert_eq!(short_profit, (true, 1_000_000_000));
+
+        let short_loss = get_pnl(size, avg_entry_price, 220_000_000_000, Direction::Short);
+        assert_eq!(short_loss, (false, 1_000_000_000));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{Cache, Config, Vault, VaultCache},
+};
+
+use super::{get_amount_after_fee, get_swap_fee_bps, token_to_usd, usd_to_token};
+
+/// The result of a swap between two vaults.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    /// The amount of the output asset, after fees.
+    pub amount_out: u64,
+    /// The USD value of the input asset.
+    pub usd_amount: u128,
+    /// The fee charged, denominated in basis points.
+    pub fee_bps: u64,
+}
+
+/// Swaps the given amount of the input vault's asset for the output vault's asset.
+///
+/// The input is valued at the lower bound of the input vault's oracle price and converted to
+/// the output asset at the upper bound of the output vault's oracle price. The fee is kept in the
+/// output vault, which means that it accrues to LPs. The output is capped by the output vault's
+/// available liquidity, given the amount held by its token account.
+pub fn swap_vaults(
+    config: &Config,
+    cache: &Cache,
+    vault_in: &mut Vault,
+    vault_in_cache: &VaultCache,
+    vault_out: &mut Vault,
+    vault_out_cache: &VaultCache,
+    token_vault_out_amount: u64,
+    amount_in: u64,
+) -> Result<SwapResult> {
+    let usd_amount = token_to_usd(
+        amount_in.into(),
+        vault_in_cache.get_price(false),
+        vault_in.decimals,
+    )?;
+    let amount_out = usd_to_token(
+        usd_amount,
+        vault_out_cache.get_price(true),
+        vault_out.decimals,
+    )?;
+    let fee_bps = get_swap_fee_bps(config, cache, vault_in, vault_out, usd_amount);
+
+    let amount_out: u64 = get_amount_after_fee(amount_out, fee_bps)?
+        .try_into()
+        .or(Err(ErrorCode::InvalidTokenAmount))?;
+    require!(amount_out > 0, ErrorCode::InvalidTokenAmount);
+
+    require!(
+        vault_out.get_available_liquidity(token_vault_out_amount) >= amount_out.into(),
+        ErrorCode::InsufficientLiquidityForSwap
+    );
+
+    vault_in.increase_pool_amount(amount_in.into());
+    vault_in.increase_debt_amount(usd_amount);
+
+    vault_out.decrease_pool_amount(amount_out.into())?;
+    // the debt might have been accrued at a different price, so we never go below zero
+    let debt_delta = usd_amount.min(vault_out.debt_amount);
+    vault_out.decrease_debt_amount(debt_delta)?;
+
+    Ok(SwapResult {
+        amount_out,
+        usd_amount,
+        fee_bps,
+    })
+}